@@ -1,10 +1,10 @@
-use snafu::OptionExt;
+use snafu::{OptionExt, ResultExt};
 
 use crate::domain::objects::Config;
 use crate::services::toml_service::{TomlFileService, TomlFileServiceTrait};
 use crate::services::file_service::{FileService, FileServiceTrait};
 use crate::services::config_file_service::ConfigFileService;
-use crate::domain::errors::config_builder::{ConfigBuilderError, NoneError};
+use crate::domain::errors::config_builder::{ConfigBuilderError, NoneError, ReadConfig};
 
 
 pub struct ConfigBuilder {
@@ -43,16 +43,17 @@ impl ConfigBuilder {
             self.toml_service.context(NoneError)?
         );
 
-        return Ok(config_file_service.read_config());
+        return config_file_service.read_config().context(ReadConfig);
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
     #[test]
     fn test_builder_found_config() {
-        let expected = Config{lex_office_api_key: Some("apikey".to_string())};
+        let expected = Config{lex_office_api_key: Some("apikey".to_string()), ..Config::default()};
 
         let config = ConfigBuilder::new()
             .using_toml()
@@ -64,7 +65,7 @@ mod test {
 
     #[test]
     fn test_builder_default_config() {
-        let expected = Config{lex_office_api_key: None};
+        let expected = Config::default();
 
         let config = ConfigBuilder::new()
             .using_toml()
@@ -75,6 +76,16 @@ mod test {
     }
 
 
+    #[test]
+    fn test_builder_invalid_config() {
+        let config = ConfigBuilder::new()
+            .using_toml()
+            .from("test_helpers/invalid_config.toml".to_owned())
+            .build();
+
+        assert_eq!(config.is_err(), true)
+    }
+
     #[test]
     fn test_builder_error() {
         let config = ConfigBuilder::new()
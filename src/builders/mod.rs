@@ -1 +1,2 @@
-pub mod config_builder;
\ No newline at end of file
+pub mod config_builder;
+pub mod store_builder;
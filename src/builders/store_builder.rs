@@ -0,0 +1,126 @@
+use snafu::ResultExt;
+
+use std::path::Path;
+
+use crate::constants::{AUDIT_LOG_FILE, BACKUP_DIRECTORY, PROJECTS_DATA_FILE, PROJECTS_SQLITE_FILE};
+use crate::domain::errors::project_data::{ProjectDataError, ResolvePath};
+use crate::domain::objects::{Encoding, StorageBackend};
+use crate::domain::objects::project_data::ProjectData;
+use crate::services::audit_log_service::AuditLogService;
+use crate::services::backup_service::BackupService;
+use crate::services::file_database_service::ProjectDataService;
+use crate::services::file_path_service::FilePathService;
+use crate::services::memory_store::MemoryStore;
+use crate::services::project_store::ProjectStore;
+use crate::services::rustbreak_store;
+use crate::services::sqlite_store::SqliteStore;
+
+
+pub struct StoreBuilder {
+    backend: StorageBackend,
+    encoding: Encoding
+}
+
+
+impl StoreBuilder {
+    pub fn new() -> StoreBuilder {
+        StoreBuilder {
+            backend: StorageBackend::default(),
+            encoding: Encoding::default()
+        }
+    }
+
+    pub fn using(self, backend: StorageBackend) -> StoreBuilder {
+        StoreBuilder {
+            backend,
+            encoding: self.encoding
+        }
+    }
+
+    /// Encoding of new rustbreak databases, existing files keep the encoding they were written in.
+    pub fn encoded(self, encoding: Encoding) -> StoreBuilder {
+        StoreBuilder {
+            backend: self.backend,
+            encoding
+        }
+    }
+
+    /// Path of the data file of the chosen backend.
+    pub fn path(&self) -> Result<String, ProjectDataError> {
+        let path = match self.backend {
+            StorageBackend::Rustbreak => PROJECTS_DATA_FILE.to_string(),
+            StorageBackend::Sqlite => PROJECTS_SQLITE_FILE.to_string()
+        };
+
+        return FilePathService::absolute_path(&path).context(ResolvePath);
+    }
+
+    /// Backups of the chosen backend's data file.
+    pub fn backups(&self, keep: usize) -> Result<BackupService, ProjectDataError> {
+        let path = self.path()?;
+        let prefix = Path::new(&path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let directory = FilePathService::absolute_path(&BACKUP_DIRECTORY.to_string()).context(ResolvePath)?;
+
+        return Ok(BackupService::new(directory, prefix, keep));
+    }
+
+    /// The audit log is shared by all backends, so history survives migrations.
+    pub fn audit_log(&self) -> Result<AuditLogService, ProjectDataError> {
+        let path = FilePathService::absolute_path(&AUDIT_LOG_FILE.to_string()).context(ResolvePath)?;
+
+        return Ok(AuditLogService::new(path));
+    }
+
+    /// Builds the store and wraps it in a `ProjectDataService` with backups and the audit log.
+    pub fn build_service(self, backup_count: usize) -> Result<ProjectDataService, ProjectDataError> {
+        let backups = self.backups(backup_count)?;
+        let audit_log = self.audit_log()?;
+        let store = self.build()?;
+
+        return ProjectDataService::new(store)
+            .with_backups(backups)
+            .with_audit_log(audit_log);
+    }
+
+    /// Builds a service over a copy of the data that is never written back. The data file is neither locked nor migrated,
+    /// so reading never waits for or changes a database that is in use, e.g. when completing on the command line.
+    pub fn build_read_only(self) -> Result<ProjectDataService, ProjectDataError> {
+        let path = self.path()?;
+        let data = match self.backend {
+            StorageBackend::Rustbreak => match rustbreak_store::detect_encoding(&path)? {
+                Some(encoding) => rustbreak_store::read_encoded(&path, &encoding)?,
+                None => ProjectData::default()
+            },
+            StorageBackend::Sqlite if !Path::new(&path).exists() => ProjectData::default(),
+            StorageBackend::Sqlite => SqliteStore::read_snapshot(&path)?
+        };
+
+        return Ok(ProjectDataService::new(Box::new(MemoryStore::new(data))));
+    }
+
+    pub fn build(self) -> Result<Box<dyn ProjectStore>, ProjectDataError> {
+        let path = self.path()?;
+
+        return match self.backend {
+            StorageBackend::Rustbreak => rustbreak_store::open(path, self.encoding),
+            StorageBackend::Sqlite => Ok(Box::new(SqliteStore::new(path)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_path_per_backend() {
+        let rustbreak = StoreBuilder::new().path().unwrap();
+        let sqlite = StoreBuilder::new().using(StorageBackend::Sqlite).path().unwrap();
+
+        assert_eq!(rustbreak.ends_with(".hou-rs/data/projects"), true);
+        assert_eq!(sqlite.ends_with(".hou-rs/data/projects.sqlite"), true);
+    }
+}
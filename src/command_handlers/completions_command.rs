@@ -0,0 +1,171 @@
+use clap::{App, Arg, ArgSettings};
+use snafu::ResultExt;
+
+use crate::domain::errors::app::{AppError, Storage, Validation};
+use crate::services::file_database_service::ProjectDataService;
+
+const BASH_SCRIPT: &str = r#"_hours() {
+    local IFS=$'\n'
+    COMPREPLY=($(hours __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null))
+}
+complete -o default -F _hours hours
+"#;
+
+const ZSH_SCRIPT: &str = r#"#compdef hours
+_hours() {
+    local -a candidates
+    candidates=("${(@f)$(hours __complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}")
+    compadd -a candidates
+}
+compdef _hours hours
+"#;
+
+const FISH_SCRIPT: &str = r#"complete -c hours -f -a '(hours __complete -- (commandline -opc)[2..-1] (commandline -ct) 2>/dev/null)'
+"#;
+
+
+pub fn handle_script(shell: Option<&str>) -> Result<String, AppError> {
+    return match shell {
+        Some("bash") => Ok(BASH_SCRIPT.trim_end().to_string()),
+        Some("zsh") => Ok(ZSH_SCRIPT.trim_end().to_string()),
+        Some("fish") => Ok(FISH_SCRIPT.trim_end().to_string()),
+        Some(other) => Validation { message: format!("No completions for {}, expected bash, zsh or fish", other) }.fail(),
+        None => Validation { message: "Give the shell to generate completions for" }.fail()
+    }
+}
+
+/// Candidates for the last of `words`, the arguments typed after `hours`. Positional arguments without fixed values
+/// are completed by `values_for`, which gets the name of the argument.
+pub fn handle_complete<F>(app: &App, words: &[&str], values_for: F) -> Result<String, AppError>
+    where F: Fn(&str) -> Result<Vec<String>, AppError>
+{
+    let (current, typed) = match words.split_last() {
+        Some((current, typed)) => (*current, typed),
+        None => ("", &[][..])
+    };
+
+    let mut command = app;
+    let mut positionals = 0;
+    let mut previous: Option<&Arg> = None;
+    for word in typed {
+        if previous.map(|arg| arg.is_set(ArgSettings::TakesValue)).unwrap_or(false) {
+            previous = None;
+            continue
+        }
+        previous = find_option(command, word);
+        if previous.is_some() || word.starts_with('-') {
+            continue
+        }
+        match command.get_subcommands().iter().find(|c| c.get_name() == *word) {
+            Some(subcommand) if positionals == 0 => command = subcommand,
+            _ => positionals += 1
+        }
+    }
+
+    let candidates = match previous {
+        Some(option) if option.is_set(ArgSettings::TakesValue) =>
+            option.get_possible_values().unwrap_or(&[]).iter().map(|v| v.to_string()).collect(),
+        _ if current.starts_with('-') => command.get_arguments().iter()
+            .filter(|arg| !arg.is_set(ArgSettings::Hidden))
+            .filter_map(|arg| arg.get_long())
+            .map(|long| format!("--{}", long))
+            .collect(),
+        _ if command.has_subcommands() && positionals == 0 => command.get_subcommands().iter()
+            .filter(|c| !c.is_set(clap::AppSettings::Hidden))
+            .map(|c| c.get_name().to_string())
+            .collect(),
+        _ => match command.get_arguments().iter().filter(|arg| is_positional(arg)).nth(positionals) {
+            Some(arg) => match arg.get_possible_values() {
+                Some(values) => values.iter().map(|v| v.to_string()).collect(),
+                None => values_for(arg.get_name())?
+            },
+            None => vec![]
+        }
+    };
+
+    let matching: Vec<String> = candidates.into_iter()
+        .filter(|candidate| candidate.starts_with(current))
+        .collect();
+
+    return Ok(matching.join("\n"))
+}
+
+/// Ids and aliases of all projects, they contain no spaces and work wherever a project name is expected.
+pub fn project_names(service: &ProjectDataService) -> Result<Vec<String>, AppError> {
+    let mut names = vec![];
+
+    for (project_id, project) in service.get_projects().context(Storage)? {
+        names.push(project_id);
+        names.extend(project.aliases);
+    }
+
+    return Ok(names)
+}
+
+pub fn invoice_numbers(service: &ProjectDataService) -> Result<Vec<String>, AppError> {
+    return Ok(service.get_invoices().context(Storage)?.into_iter().map(|i| i.number).collect())
+}
+
+/// Tasks of the selected project.
+pub fn task_names(service: &ProjectDataService) -> Result<Vec<String>, AppError> {
+    let project = match service.get_selected_project_id().context(Storage)? {
+        Some(project_id) => service.get_project(&project_id).context(Storage)?,
+        None => None
+    };
+
+    let mut tasks: Vec<String> = project.map(|p| p.tasks.into_iter().collect()).unwrap_or_default();
+    tasks.sort();
+
+    return Ok(tasks)
+}
+
+fn find_option<'a, 'b>(command: &'a App<'b>, word: &str) -> Option<&'a Arg<'b>> {
+    return command.get_arguments().iter().find(|arg| {
+        arg.get_long().map(|long| word == format!("--{}", long)).unwrap_or(false)
+            || arg.get_short().map(|short| word == format!("-{}", short)).unwrap_or(false)
+    })
+}
+
+fn is_positional(arg: &Arg) -> bool {
+    return arg.get_long().is_none() && arg.get_short().is_none();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn app() -> App<'static> {
+        return App::new("hours").subcommands(vec![
+            App::new("for").args(vec![
+                Arg::with_name("project_name").takes_value(true),
+                Arg::with_name("unit").long("unit").takes_value(true).possible_values(&["hour", "day"]),
+                Arg::with_name("new").long("new")
+            ]),
+            App::new("fix"),
+            App::new("__complete").setting(clap::AppSettings::Hidden)
+        ])
+    }
+
+    fn complete(words: &[&str]) -> String {
+        return handle_complete(&app(), words, |arg| Ok(vec![format!("{}-a", arg), format!("{}-b", arg)])).unwrap();
+    }
+
+    #[test]
+    fn test_complete_subcommands() {
+        assert_eq!(complete(&[""]), "for\nfix");
+        assert_eq!(complete(&["fo"]), "for");
+    }
+
+    #[test]
+    fn test_complete_positional_values() {
+        assert_eq!(complete(&["for", ""]), "project_name-a\nproject_name-b");
+        assert_eq!(complete(&["for", "--new", "project_name-b"]), "project_name-b");
+        assert_eq!(complete(&["for", "x", ""]), "");
+    }
+
+    #[test]
+    fn test_complete_options() {
+        assert_eq!(complete(&["for", "--"]), "--unit\n--new");
+        assert_eq!(complete(&["for", "x", "--unit", "d"]), "day");
+    }
+}
@@ -0,0 +1,266 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use snafu::ResultExt;
+
+use crate::builders::store_builder::StoreBuilder;
+use crate::domain::errors::app::{AppError, ReadInput, Storage, Validation};
+use crate::domain::errors::project_data::{EmptyBackup, InvalidBackup, LossyConversion, ProjectDataError, TargetExists, WriteDataFile};
+use crate::domain::objects::{Config, Encoding, StorageBackend};
+use crate::domain::objects::integrity::Problem;
+use crate::domain::objects::project_data::ProjectData;
+use crate::services::audit_log_service::AuditLogService;
+use crate::services::integrity_service::IntegrityService;
+use crate::services::rustbreak_store;
+use crate::services::schema_migrations;
+use crate::services::sqlite_store::SqliteStore;
+
+
+pub fn handle_migrate(config: &Config, to: Option<&str>, check: bool) -> Result<String, AppError> {
+    if check {
+        return check_migrations(config)
+    }
+
+    let target = match to {
+        Some("sqlite") => StorageBackend::Sqlite,
+        Some(other) => return Validation { message: format!("Can not migrate to {}", other) }.fail(),
+        None => return Validation { message: "Give the backend to migrate to with --to, or --check for pending schema migrations" }.fail()
+    };
+
+    let source = StoreBuilder::new().using(StorageBackend::Rustbreak);
+    let target_builder = StoreBuilder::new().using(target);
+    let target_path = target_builder.path().context(Storage)?;
+
+    if Path::new(&target_path).exists() {
+        return TargetExists { path: target_path }.fail().context(Storage)
+    }
+
+    let audit_log = source.audit_log().context(Storage)?;
+    let data = source.build().context(Storage)?.snapshot().context(Storage)?;
+    let entries = data.billable.len();
+    let projects = data.projects.len();
+
+    // Written past the service, so the audit log continues from a snapshot of the migrated data
+    let snapshot = AuditLogService::snapshot_event(&data).context(Storage)?;
+    let store = target_builder.build().context(Storage)?;
+    store.replace(data).context(Storage)?;
+    store.save().context(Storage)?;
+    audit_log.append(&[snapshot]).context(Storage)?;
+
+    return Ok(format!(
+        "Migrated {} projects and {} entries to {}, set storage = \"sqlite\" in your config to use it",
+        projects, entries, target_path
+    ))
+}
+
+/// Dry run of the schema migrations the configured database needs, nothing is written.
+fn check_migrations(config: &Config) -> Result<String, AppError> {
+    let path = StoreBuilder::new().using(config.storage.clone()).path().context(Storage)?;
+
+    if config.storage == StorageBackend::Sqlite {
+        return Ok(match SqliteStore::pending_migrations(&path).context(Storage)? {
+            None => format!("No database at {} yet", path),
+            Some(0) => format!("{} is up to date", path),
+            Some(pending) => format!("{} needs {} schema migrations, they are applied the next time it is opened", path, pending)
+        })
+    }
+
+    let version = match rustbreak_store::check_schema(&path).context(Storage)? {
+        Some(version) => version,
+        None => return Ok(format!("No database at {} yet", path))
+    };
+    let pending = schema_migrations::pending(version);
+
+    if pending.is_empty() {
+        return Ok(format!("{} is up to date at schema version {}", path, version))
+    }
+
+    let mut lines = vec![format!(
+        "{} is at schema version {}, the next save migrates it to version {}:",
+        path, version, schema_migrations::CURRENT_VERSION
+    )];
+    lines.extend(pending.iter().map(|m| format!("  - {}", m.description)));
+
+    return Ok(lines.join("\n"))
+}
+
+pub fn handle_convert(config: &Config, to: Option<&str>) -> Result<String, AppError> {
+    let target = match to {
+        Some("yaml") => Encoding::Yaml,
+        Some("bin") => Encoding::Bin,
+        Some("ron") => Encoding::Ron,
+        Some(other) => return Validation { message: format!("Unknown encoding {}", other) }.fail(),
+        None => return Validation { message: "Give the encoding to convert to with --to" }.fail()
+    };
+
+    if config.storage != StorageBackend::Rustbreak {
+        return Validation { message: "Only the rustbreak database can be converted" }.fail()
+    }
+
+    let path = StoreBuilder::new().using(StorageBackend::Rustbreak).path().context(Storage)?;
+    // Keeps the database locked while it is rewritten
+    let store = rustbreak_store::open(path.clone(), config.encoding.clone()).context(Storage)?;
+    let data = store.snapshot().context(Storage)?;
+
+    let converted_path = format!("{}.converting", path);
+    rustbreak_store::write_encoded(&converted_path, &target, &data).context(Storage)?;
+
+    if rustbreak_store::read_encoded(&converted_path, &target).context(Storage)? != data {
+        let _ = fs::remove_file(&converted_path);
+        return LossyConversion { path, encoding: to.unwrap_or_default() }.fail().context(Storage)
+    }
+
+    fs::rename(&converted_path, &path).context(WriteDataFile { path: &path }).context(Storage)?;
+
+    return Ok(format!("Converted {} to {}", path, to.unwrap_or_default()))
+}
+
+pub fn handle_backup(config: &Config, list: bool) -> Result<String, AppError> {
+    let builder = StoreBuilder::new()
+        .using(config.storage.clone())
+        .encoded(config.encoding.clone());
+    // Explicit backups are made even when automatic ones are disabled
+    let backups = builder.backups(config.backup_count.max(1)).context(Storage)?;
+
+    if list {
+        let names = backups.list().context(Storage)?;
+
+        if names.is_empty() {
+            return Ok("No backups yet".to_string())
+        }
+        return Ok(names.join("\n"))
+    }
+
+    let store = builder.build().context(Storage)?;
+
+    return match backups.backup(&*store).context(Storage)? {
+        Some(path) => Ok(format!("Backed up to {}", path)),
+        None => Ok("Nothing to back up yet".to_string())
+    }
+}
+
+/// Replaces the database with a backup. Backups that hold neither projects nor entries are only restored when `force` is given.
+pub fn handle_restore(config: &Config, snapshot: Option<&str>, force: bool) -> Result<String, AppError> {
+    let snapshot = snapshot.unwrap_or_default().to_string();
+    let builder = StoreBuilder::new()
+        .using(config.storage.clone())
+        .encoded(config.encoding.clone());
+    let backups = builder.backups(config.backup_count.max(1)).context(Storage)?;
+
+    let path = backups.resolve(&snapshot).context(Storage)?;
+    let data = read_backup(&config.storage, &path).context(Storage)?;
+    check_restorable(&path, &data, force).context(Storage)?;
+
+    // Saving backs up the data that is replaced
+    let service = builder.build_service(config.backup_count.max(1)).context(Storage)?;
+    service.restore(data).context(Storage)?;
+    service.write_to_file().context(Storage)?;
+
+    return Ok(format!("Restored {}", snapshot))
+}
+
+pub fn handle_check(config: &Config, fix: bool) -> Result<String, AppError> {
+    let service = StoreBuilder::new()
+        .using(config.storage.clone())
+        .encoded(config.encoding.clone())
+        .build_service(config.backup_count)
+        .context(Storage)?;
+    let issues = IntegrityService::check(&service.snapshot().context(Storage)?);
+
+    if issues.is_empty() {
+        return Ok("No problems found".to_string())
+    }
+
+    let mut lines = vec![];
+    let (mut fixed, mut fixable) = (0, 0);
+    for issue in &issues {
+        let billed = match &issue.problem {
+            Problem::DuplicateEntry { entry } => service.is_billed(entry.id).context(Storage)?,
+            _ => false
+        };
+        let repair = fix && !billed && (issue.problem.is_fixable()
+            || (issue.problem.needs_confirmation() && confirm(&format!("{}, remove it?", issue))?));
+        if repair && service.repair(&issue.problem).context(Storage)? {
+            fixed += 1;
+            lines.push(format!("{} (fixed)", issue));
+        } else if billed {
+            lines.push(format!("{} (on an invoice, kept)", issue));
+        } else if issue.problem.needs_confirmation() && !fix {
+            fixable += 1;
+            lines.push(format!("{} (fixable after confirmation)", issue));
+        } else if issue.problem.is_fixable() && !fix {
+            fixable += 1;
+            lines.push(format!("{} (fixable)", issue));
+        } else {
+            lines.push(issue.to_string());
+        }
+    }
+
+    if fix {
+        // Without repairs there is nothing to save or back up
+        if fixed > 0 {
+            service.write_to_file().context(Storage)?;
+        }
+        lines.push(format!("{} problems found, {} fixed", issues.len(), fixed));
+    } else {
+        lines.push(format!("{} problems found, {} can be fixed with --fix", issues.len(), fixable));
+    }
+
+    return Ok(lines.join("\n"))
+}
+
+/// Asks on stderr whether to go ahead, anything but yes is no.
+fn confirm(question: &str) -> Result<bool, AppError> {
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush().context(ReadInput)?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).context(ReadInput)?;
+
+    return Ok(["y", "yes"].contains(&answer.trim().to_lowercase().as_str()));
+}
+
+/// Refuses backups that hold neither projects nor entries, e.g. of a database that was just created, unless `force` is given.
+fn check_restorable(path: &String, data: &ProjectData, force: bool) -> Result<(), ProjectDataError> {
+    if data.projects.is_empty() && data.billable.is_empty() && !force {
+        return EmptyBackup { path }.fail()
+    }
+
+    return Ok(());
+}
+
+/// Reads a backup, making sure it is a complete `ProjectData` of the given backend.
+fn read_backup(backend: &StorageBackend, path: &String) -> Result<ProjectData, ProjectDataError> {
+    let data = match backend {
+        StorageBackend::Rustbreak => match rustbreak_store::detect_encoding(path) {
+            Ok(Some(encoding)) => rustbreak_store::read_encoded(path, &encoding),
+            _ => return InvalidBackup { path }.fail()
+        },
+        StorageBackend::Sqlite => SqliteStore::read_snapshot(path)
+    };
+
+    return data.map_err(|_| ProjectDataError::InvalidBackup { path: path.to_string() });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::domain::objects::project_data::{BillableUnit, LineGrouping, Project};
+
+    #[test]
+    fn test_restore_backup_without_entries() {
+        let mut data = ProjectData::default();
+        data.projects.insert("foo".to_string(), Project { name: "Foo".to_string(), unit_price: 80, unit: BillableUnit::Hour, tasks: HashSet::new(), aliases: vec![], address: vec![], grouping: LineGrouping::Task });
+
+        assert_eq!(check_restorable(&"backup".to_string(), &data, false).is_ok(), true);
+    }
+
+    #[test]
+    fn test_restore_empty_backup_needs_force() {
+        let data = ProjectData::default();
+
+        assert_eq!(check_restorable(&"backup".to_string(), &data, false).is_err(), true);
+        assert_eq!(check_restorable(&"backup".to_string(), &data, true).is_ok(), true);
+    }
+}
@@ -1,12 +1,26 @@
-//use crate::builders::config_builder::ConfigBuilder;
+use snafu::ResultExt;
 
+use crate::domain::errors::app::{AppError, Storage, Validation};
+use crate::domain::errors::project_data::ProjectDataError;
+use crate::services::file_database_service::ProjectDataService;
 
-pub fn handle(project_name: Option<&str>) -> String {
-//    let config = ConfigBuilder::new();
 
-    if let Some(name) = project_name {
-        return format!("{}", name)
+pub fn handle(service: &ProjectDataService, project_name: Option<&str>) -> Result<String, AppError> {
+    let name = project_name.unwrap_or_default().to_string();
+
+    match service.select_project(&name) {
+        Err(ProjectDataError::UnknownProject { suggestions, .. }) => return Validation {
+            message: format!(
+                "Project {} does not exist yet, create it with `hours project add {} --rate <rate>`{}",
+                name, name, suggestions
+            )
+        }.fail(),
+        result => result.context(Storage)?
     }
+    service.write_to_file().context(Storage)?;
+
+    let project_id = service.resolve_project_id(&name).context(Storage)?;
+    let project = service.get_project(&project_id).context(Storage)?;
 
-    return String::new()
-}
\ No newline at end of file
+    return Ok(project.map(|p| p.name).unwrap_or(name))
+}
@@ -0,0 +1,61 @@
+use snafu::ResultExt;
+
+use crate::builders::store_builder::StoreBuilder;
+use crate::domain::errors::app::{AppError, Storage};
+use crate::domain::objects::Config;
+use crate::domain::objects::audit::AuditEvent;
+use crate::services::audit_log_service::AuditLogService;
+
+const DEFAULT_LIMIT: usize = 20;
+
+
+pub fn handle(config: &Config, limit: Option<&str>, replay: bool) -> Result<String, AppError> {
+    let builder = StoreBuilder::new()
+        .using(config.storage.clone())
+        .encoded(config.encoding.clone());
+    let events = builder.audit_log().context(Storage)?.read().context(Storage)?;
+
+    if replay {
+        let data = AuditLogService::replay(&events).context(Storage)?;
+        let service = builder.build_service(config.backup_count.max(1)).context(Storage)?;
+        service.restore(data).context(Storage)?;
+        service.write_to_file().context(Storage)?;
+
+        return Ok(format!("Rebuilt the database from {} events", events.len()))
+    }
+
+    let limit = match limit {
+        None => DEFAULT_LIMIT,
+        Some(l) => l.parse::<usize>().ok().ok_or(AppError::Validation {
+            message: format!("Invalid limit {}, expected a whole number", l)
+        })?
+    };
+
+    if events.is_empty() {
+        return Ok("No history yet".to_string())
+    }
+
+    let lines: Vec<String> = events.iter()
+        .skip(events.len().saturating_sub(limit))
+        .map(format_event)
+        .collect();
+
+    return Ok(lines.join("\n"))
+}
+
+pub fn format_event(event: &AuditEvent) -> String {
+    let after = event.after.as_ref().map(|a| a.to_string()).unwrap_or_else(|| "-".to_string());
+    let operation = match event.undoes {
+        Some(_) => format!("undo {}", event.operation),
+        None => event.operation.to_string()
+    };
+
+    return format!(
+        "{}  {}  {}  {}  {}",
+        event.timestamp.get(..19).unwrap_or(&event.timestamp),
+        event.user.as_deref().unwrap_or("-"),
+        operation,
+        event.target,
+        after
+    )
+}
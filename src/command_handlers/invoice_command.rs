@@ -0,0 +1,328 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::Path;
+use chrono::{Local, NaiveDate};
+use snafu::{OptionExt, ResultExt};
+use tempfile::NamedTempFile;
+
+use crate::command_handlers::{invoices_command, sync_command};
+use crate::domain::errors::app::{AppError, Api, NotSubmitted, ReadInput, Storage, Template, Validation, WriteInvoice};
+use crate::domain::objects::{BankDetails, Config, Sender};
+use crate::domain::objects::invoice::{Invoice, ServicePeriod};
+use crate::domain::objects::project_data::{Billable, BillingFilter, Project, Quantity};
+use crate::services::date_service::{DateService, ACCEPTED_FORMATS};
+use crate::services::file_database_service::ProjectDataService;
+use crate::services::invoice_service::InvoiceService;
+use crate::services::lex_office_service::LexOfficeService;
+use crate::services::pdf_service::PdfService;
+use crate::services::template_service::TemplateService;
+
+
+/// Options of the `invoice` command.
+#[derive(Debug, Default)]
+pub struct InvoiceOptions<'a> {
+    /// File the PDF is written to
+    pub pdf: Option<&'a str>,
+    /// Template name and the file it is rendered to
+    pub template: Option<(&'a str, &'a str)>,
+    /// Service period as `start..end`
+    pub period: Option<&'a str>,
+    /// First day of the entries to bill, without it all unbilled entries are billed
+    pub from: Option<&'a str>,
+    /// Last day of the entries to bill, today when not given
+    pub until: Option<&'a str>,
+    /// Tasks to bill, all when empty
+    pub tasks: Vec<&'a str>,
+    /// Asks which of the entries to leave for a later invoice
+    pub pick: bool,
+    /// Shows the invoice and the LexOffice request without saving or sending anything
+    pub dry_run: bool,
+    /// Creates the invoice in LexOffice
+    pub lex_office: bool
+}
+
+pub fn handle(config: &Config, service: &ProjectDataService, project_name: Option<&str>, options: &InvoiceOptions) -> Result<String, AppError> {
+    let name = project_name.unwrap_or_default().to_string();
+    let project_id = service.resolve_project_id(&name).context(Storage)?;
+    let date = Local::now().date().naive_local();
+    let period = match options.period {
+        Some(input) => Some(parse_period(input, date)?),
+        None => None
+    };
+    let filter = billing_filter(service, &project_id, options, date)?;
+
+    let mut billing = service.get_unbilled(&project_id, &filter).context(Storage)?;
+    if options.pick && !billing.is_empty() {
+        billing = pick(billing)?;
+    }
+    let total: Quantity = billing.iter().map(|b| b.quantity).sum();
+    let mut lines = vec![format!("{}: {} entries, {} in total", project_id, billing.len(), total)];
+
+    let (pdf, template) = (options.pdf, options.template);
+    if options.dry_run || options.lex_office || pdf.is_some() || template.is_some() {
+        if billing.is_empty() {
+            let scope = if filter == (BillingFilter { until: Some(date), ..BillingFilter::default() }) { "until today" } else { "selected" };
+            return Validation { message: format!("{} has no unbilled entries {}", project_id, scope) }.fail()
+        }
+        let project = service.get_project(&project_id).context(Storage)?.context(Validation {
+            message: format!("Unknown project {}", project_id)
+        })?;
+
+        if options.dry_run {
+            let number = service.peek_invoice_number(&config.invoice_numbers, date).context(Storage)?;
+            let invoice = InvoiceService::invoice(&number, config.tax_rate, &project_id, &project, &billing, date, period);
+            let (url, body) = LexOfficeService::invoice_request(&invoice);
+            lines.push("Dry run, nothing is saved or sent".to_string());
+            lines.push(invoices_command::describe(&invoice));
+            lines.push(format!("POST {}", url));
+            lines.push(format!("{:#}", body));
+            return Ok(lines.join("\n"))
+        }
+        let lex_office = match options.lex_office {
+            true => Some(LexOfficeService::new(config).context(Api)?),
+            false => None
+        };
+        // Saved only once the documents are rendered, so that failed attempts do not use up numbers.
+        // LexOffice numbers the invoices it creates, until then they are kept under a reference.
+        let number = match lex_office {
+            Some(_) => service.lex_office_reference(date).context(Storage)?,
+            None => service.issue_invoice_number(&config.invoice_numbers, date).context(Storage)?
+        };
+        let mut invoice = InvoiceService::invoice(&number, config.tax_rate, &project_id, &project, &billing, date, period);
+        match lex_office {
+            Some(_) => lines.push(format!("Reference {}, LexOffice assigns the invoice number", number)),
+            None => lines.push(format!("Invoice number {}", number))
+        }
+        if let Some(period) = &invoice.service_period {
+            lines.push(format!("Service period {} to {}", period.start, period.end));
+        }
+
+        // Staged next to their paths and moved there after saving, so that no document carries a number that is not saved
+        let mut documents = vec![];
+        if let Some(path) = pdf {
+            documents.push((path, stage(path, &render_pdf(config, &invoice, &project)?)?));
+            lines.push(format!("Wrote invoice to {}", path));
+        }
+        if let Some((name, path)) = template {
+            documents.push((path, stage(path, render_template(config, &invoice, &project, &billing, name, path)?.as_bytes())?));
+            lines.push(format!("Wrote {} to {}", name, path));
+        }
+        if lex_office.is_some() {
+            invoice.submission = Some(sync_command::queue());
+        }
+        service.add_invoice(invoice).context(Storage)?;
+        service.write_to_file().context(Storage)?;
+        for (path, staged) in documents.into_iter() {
+            staged.persist(path).map_err(|e| e.error).context(WriteInvoice { path })?;
+        }
+        // After saving, the entries count as billed however the submission goes
+        if let Some(lex_office) = &lex_office {
+            match sync_command::submit(service, lex_office, &number) {
+                Ok(line) => lines.push(line),
+                Err(e) => {
+                    lines.push(e.to_string());
+                    return NotSubmitted { message: lines.join("\n") }.fail()
+                }
+            }
+        }
+    }
+
+    return Ok(lines.join("\n"))
+}
+
+/// Cancels the invoice `number`. Invoices created in LexOffice get a credit note there instead of a PDF,
+/// it is queued and saved with the cancellation before it is sent, like invoices.
+pub fn handle_cancel(config: &Config, service: &ProjectDataService, number: Option<&str>, pdf: Option<&str>) -> Result<String, AppError> {
+    let number = number.unwrap_or_default().to_string();
+    let invoice = service.get_invoice(&number).context(Storage)?;
+    let date = Local::now().date().naive_local();
+    let lex_office = match invoice.lex_office_id {
+        Some(_) if pdf.is_some() => return Validation {
+            message: format!("Invoice {} is in LexOffice, LexOffice numbers and renders its credit note", number)
+        }.fail(),
+        Some(_) => Some(LexOfficeService::new(config).context(Api)?),
+        None => None
+    };
+
+    let cancellation = service.cancel_invoice(&number, &config.invoice_numbers, date).context(Storage)?;
+    let mut lines = vec![
+        format!("Cancelled invoice {} with {}", number, cancellation.number),
+        format!("{} entries can be billed again", invoice.entries.len())
+    ];
+
+    let staged = match pdf {
+        Some(path) => {
+            let (sender, bank) = sender_and_bank(config)?;
+            let draft = InvoiceService::draft_of(&cancellation, sender, bank).context(Validation {
+                message: format!("Invoice {} has an invalid date", cancellation.number)
+            })?;
+            Some((path, stage(path, &PdfService::render(&draft))?))
+        },
+        None => None
+    };
+    if lex_office.is_some() {
+        service.queue_submission(&cancellation.number, sync_command::queue()).context(Storage)?;
+    }
+    service.write_to_file().context(Storage)?;
+    if let Some((path, staged)) = staged {
+        staged.persist(path).map_err(|e| e.error).context(WriteInvoice { path })?;
+        lines.push(format!("Wrote cancellation to {}", path));
+    }
+
+    if let Some(lex_office) = &lex_office {
+        match sync_command::submit(service, lex_office, &cancellation.number) {
+            Ok(line) => lines.push(line),
+            Err(e) => {
+                lines.push(e.to_string());
+                return NotSubmitted { message: lines.join("\n") }.fail()
+            }
+        }
+    }
+
+    return Ok(lines.join("\n"))
+}
+
+fn billing_filter(service: &ProjectDataService, project_id: &String, options: &InvoiceOptions, today: NaiveDate) -> Result<BillingFilter, AppError> {
+    let parse = |input: Option<&str>| -> Result<Option<NaiveDate>, AppError> {
+        return match input {
+            Some(input) => DateService::parse(input, today).map(Some).context(Validation {
+                message: format!("Invalid date {}, expected one of {}", input, ACCEPTED_FORMATS)
+            }),
+            None => Ok(None)
+        }
+    };
+    let (from, until) = (parse(options.from)?, parse(options.until)?);
+    if let (Some(from), Some(until)) = (from, until) {
+        if until < from {
+            return Validation { message: format!("--until {} is before --from {}", until, from) }.fail()
+        }
+    }
+
+    let mut tasks = vec![];
+    for task in options.tasks.iter() {
+        tasks.push(service.resolve_task(project_id, &task.to_string(), false).context(Storage)?);
+    }
+
+    return Ok(BillingFilter { from, until: Some(until.unwrap_or(today)), tasks });
+}
+
+/// Lists `billing` and asks which entries to leave out, they stay unbilled for the next invoice.
+/// The list and the question go to stderr, no answer at all aborts.
+fn pick(billing: Vec<Billable>) -> Result<Vec<Billable>, AppError> {
+    let mut prompt: Vec<String> = billing.iter().enumerate()
+        .map(|(index, b)| format!("{:>3}  {}  {}  {}", index + 1, b.date.format("%Y-%m-%d"), b.task, b.quantity))
+        .collect();
+    prompt.push("Entries to leave out, e.g. 2 5-7, nothing to invoice all: ".to_string());
+    eprint!("{}", prompt.join("\n"));
+    io::stderr().flush().context(ReadInput)?;
+
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).context(ReadInput)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no answer, nothing was invoiced")).context(ReadInput)
+    }
+    let excluded = parse_selection(&input, billing.len()).context(Validation {
+        message: format!("Invalid selection {}, expected entry numbers from 1 to {} or ranges like 2-4", input.trim(), billing.len())
+    })?;
+
+    return Ok(billing.into_iter()
+        .enumerate()
+        .filter(|(index, _)| !excluded.contains(&(index + 1)))
+        .map(|(_, b)| b)
+        .collect());
+}
+
+/// Numbers and ranges like `2 5-7`, separated by spaces or commas. `None` when one is outside `1..=count`.
+fn parse_selection(input: &str, count: usize) -> Option<HashSet<usize>> {
+    let mut selection = HashSet::new();
+    for part in input.split(|c: char| c == ',' || c.is_whitespace()).filter(|p| !p.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start.parse::<usize>().ok()?, end.parse::<usize>().ok()?),
+            None => (part.parse::<usize>().ok()?, part.parse::<usize>().ok()?)
+        };
+        if start == 0 || end < start || end > count {
+            return None
+        }
+        selection.extend(start..=end);
+    }
+
+    return Some(selection);
+}
+
+/// Service period given as `start..end` or a single day.
+fn parse_period(input: &str, today: NaiveDate) -> Result<ServicePeriod, AppError> {
+    let (start, end) = DateService::parse_range(input, today).context(Validation {
+        message: format!("Invalid service period {}, expected start..end or a single day, each one of {}", input, ACCEPTED_FORMATS)
+    })?;
+
+    return Ok(ServicePeriod {
+        start: start.format("%Y-%m-%d").to_string(),
+        end: end.format("%Y-%m-%d").to_string()
+    });
+}
+
+/// Writes `content` to a temporary file in the directory of `path`, to be moved to `path` once the invoice is saved.
+fn stage(path: &str, content: &[u8]) -> Result<NamedTempFile, AppError> {
+    let directory = match Path::new(path).parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new(".")
+    };
+    let mut staged = NamedTempFile::new_in(directory).context(WriteInvoice { path })?;
+    staged.write_all(content).context(WriteInvoice { path })?;
+
+    return Ok(staged);
+}
+
+fn render_template(config: &Config, invoice: &Invoice, project: &Project, billing: &[Billable], name: &str, path: &str) -> Result<String, AppError> {
+    let template = TemplateService::find(name, path).context(Template)?;
+    let document = TemplateService::document(invoice, config, project, billing);
+
+    return TemplateService::render(&template, &document).context(Template);
+}
+
+fn render_pdf(config: &Config, invoice: &Invoice, project: &Project) -> Result<Vec<u8>, AppError> {
+    let (sender, bank) = sender_and_bank(config)?;
+    if project.address.is_empty() {
+        return Validation {
+            message: format!("{} has no customer address, set it with `hours project address {} <lines>...`", invoice.project_id, invoice.project_id)
+        }.fail()
+    }
+
+    let draft = InvoiceService::draft_of(invoice, sender, bank).context(Validation {
+        message: format!("Invoice {} has an invalid date", invoice.number)
+    })?;
+
+    return Ok(PdfService::render(&draft));
+}
+
+fn sender_and_bank(config: &Config) -> Result<(&Sender, &BankDetails), AppError> {
+    let sender = config.sender.as_ref().context(Validation {
+        message: "Add a [sender] section with your name and address to the config to render invoices"
+    })?;
+    let bank = config.bank.as_ref().context(Validation {
+        message: "Add a [bank] section with your account holder and IBAN to the config to render invoices"
+    })?;
+
+    return Ok((sender, bank));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_selection() {
+        let selection = |input: &str| parse_selection(input, 7).map(|s| {
+            let mut numbers: Vec<usize> = s.into_iter().collect();
+            numbers.sort_unstable();
+            numbers
+        });
+
+        assert_eq!(selection("2 5-7"), Some(vec![2, 5, 6, 7]));
+        assert_eq!(selection("1,3, 3\n"), Some(vec![1, 3]));
+        assert_eq!(selection(""), Some(vec![]));
+        assert_eq!(selection("0"), None);
+        assert_eq!(selection("6-8"), None);
+        assert_eq!(selection("4-2"), None);
+        assert_eq!(selection("all"), None);
+    }
+}
@@ -0,0 +1,97 @@
+use snafu::ResultExt;
+
+use crate::domain::errors::app::{AppError, Storage};
+use crate::domain::objects::invoice::{format_amount, Invoice, LineItem};
+use crate::domain::objects::project_data::BillableUnit;
+use crate::services::file_database_service::ProjectDataService;
+
+
+pub fn handle_list(service: &ProjectDataService) -> Result<String, AppError> {
+    let invoices = service.get_invoices().context(Storage)?;
+
+    if invoices.is_empty() {
+        return Ok("No invoices yet, issue one with `hours invoice <project> --pdf <file>`".to_string())
+    }
+
+    let lines: Vec<String> = invoices.iter()
+        .map(|i| {
+            let queued = match &i.submission {
+                Some(submission) if submission.rejected => "  rejected by LexOffice",
+                Some(_) => "  queued for LexOffice",
+                None => ""
+            };
+            let number = match &i.lex_office_number {
+                Some(lex_office_number) => format!("{} ({})", lex_office_number, i.number),
+                None => i.number.to_string()
+            };
+            format!("{}  {}  {}  {}  {}{}", number, i.date, i.project_id, i.status, format_amount(i.gross), queued)
+        })
+        .collect();
+
+    return Ok(lines.join("\n"))
+}
+
+pub fn handle_show(service: &ProjectDataService, number: Option<&str>) -> Result<String, AppError> {
+    let number = number.unwrap_or_default().to_string();
+    let invoice = service.get_invoice(&number).context(Storage)?;
+
+    return Ok(describe(&invoice))
+}
+
+pub fn handle_mark_paid(service: &ProjectDataService, number: Option<&str>) -> Result<String, AppError> {
+    let number = number.unwrap_or_default().to_string();
+    service.mark_paid(&number).context(Storage)?;
+    service.write_to_file().context(Storage)?;
+
+    return Ok(format!("Marked invoice {} as paid", number))
+}
+
+/// Invoice with its line items, totals and billed entries.
+pub fn describe(invoice: &Invoice) -> String {
+    let mut lines = match &invoice.cancels {
+        Some(cancelled) => vec![format!("Cancellation {} ({})", invoice.number, invoice.status), format!("Cancels invoice {}", cancelled)],
+        None => vec![format!("Invoice {} ({})", invoice.number, invoice.status)]
+    };
+    lines.push(format!("Project: {}", invoice.project_id));
+    lines.push(format!("Date: {}", invoice.date));
+    if let Some(period) = &invoice.service_period {
+        lines.push(format!("Service period: {} to {}", period.start, period.end));
+    }
+    if let Some(lex_office_id) = &invoice.lex_office_id {
+        lines.push(format!("LexOffice: {}", lex_office_id));
+    }
+    if let Some(lex_office_number) = &invoice.lex_office_number {
+        lines.push(format!("LexOffice number: {}", lex_office_number));
+    }
+    if let Some(submission) = &invoice.submission {
+        match submission.rejected {
+            true => lines.push(format!("LexOffice: rejected after {} attempts, cancel it with `hours invoice cancel {}`", submission.attempts, invoice.number)),
+            false => lines.push(format!("LexOffice: queued since {}, {} attempts, send it with `hours sync`", submission.queued, submission.attempts))
+        }
+        if let Some(error) = &submission.last_error {
+            lines.push(format!("  Last error: {}", error));
+        }
+    }
+
+    lines.push("Customer:".to_string());
+    lines.extend(invoice.customer.iter().map(|line| format!("  {}", line)));
+
+    lines.push("Line items:".to_string());
+    lines.extend(invoice.line_items.iter().map(describe_line_item));
+
+    lines.push(format!("Net: {}", format_amount(invoice.net)));
+    lines.push(format!("VAT {}%: {}", invoice.tax_rate, format_amount(invoice.tax)));
+    lines.push(format!("Total: {}", format_amount(invoice.gross)));
+    lines.push(format!("Entries: {}", invoice.entries.iter().map(u64::to_string).collect::<Vec<String>>().join(", ")));
+
+    return lines.join("\n");
+}
+
+fn describe_line_item(item: &LineItem) -> String {
+    let unit = match item.unit {
+        BillableUnit::Hour => "h",
+        BillableUnit::Day => "d"
+    };
+
+    return format!("  {}  {} {} x {} = {}", item.description, item.quantity, unit, format_amount(item.unit_price as i64 * 100), format_amount(item.net()));
+}
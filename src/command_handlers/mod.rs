@@ -1 +1,10 @@
-pub mod for_command;
\ No newline at end of file
+pub mod for_command;
+pub mod worked_on_command;
+pub mod invoice_command;
+pub mod invoices_command;
+pub mod project_command;
+pub mod db_command;
+pub mod history_command;
+pub mod undo_command;
+pub mod sync_command;
+pub mod completions_command;
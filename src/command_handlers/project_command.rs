@@ -0,0 +1,109 @@
+use snafu::ResultExt;
+
+use crate::domain::errors::app::{AppError, Storage, Validation};
+use crate::domain::objects::project_data::{BillableUnit, LineGrouping, Money};
+use crate::services::file_database_service::ProjectDataService;
+
+
+pub fn handle_list(service: &ProjectDataService) -> Result<String, AppError> {
+    let projects = service.get_projects().context(Storage)?;
+
+    if projects.is_empty() {
+        return Ok("No projects yet, create one with `hours project add <project> --rate <rate>`".to_string())
+    }
+
+    let lines: Vec<String> = projects.iter()
+        .map(|(project_id, project)| match project.aliases.is_empty() {
+            true => format!("{}  {}", project_id, project.name),
+            false => format!("{}  {}  (aliases: {})", project_id, project.name, project.aliases.join(", "))
+        })
+        .collect();
+
+    return Ok(lines.join("\n"))
+}
+
+pub fn handle_add(service: &ProjectDataService, project_name: Option<&str>, rate: Option<&str>, unit: Option<&str>) -> Result<String, AppError> {
+    let name = project_name.unwrap_or_default().to_string();
+    let unit_price = parse_rate(rate.unwrap_or_default())?;
+
+    service.add_project(&name, unit_price, parse_unit(unit)?).context(Storage)?;
+    service.write_to_file().context(Storage)?;
+
+    return Ok(format!("Added project {}, select it with `hours for {}`", name, name))
+}
+
+pub fn handle_rename(service: &ProjectDataService, project_name: Option<&str>, new_name: Option<&str>) -> Result<String, AppError> {
+    let name = project_name.unwrap_or_default().to_string();
+    let new_name = new_name.unwrap_or_default().to_string();
+
+    service.rename_project(&name, &new_name).context(Storage)?;
+    service.write_to_file().context(Storage)?;
+
+    return Ok(format!("Renamed {} to {}", name, new_name))
+}
+
+pub fn handle_address(service: &ProjectDataService, project_name: Option<&str>, lines: Option<Vec<&str>>) -> Result<String, AppError> {
+    let name = project_name.unwrap_or_default().to_string();
+    let address: Vec<String> = lines.unwrap_or_default().into_iter().map(String::from).collect();
+
+    service.set_address(&name, address.clone()).context(Storage)?;
+    service.write_to_file().context(Storage)?;
+
+    return Ok(format!("Invoices for {} go to:\n{}", name, address.join("\n")))
+}
+
+pub fn handle_alias(service: &ProjectDataService, project_name: Option<&str>, alias: Option<&str>) -> Result<String, AppError> {
+    let name = project_name.unwrap_or_default().to_string();
+    let alias = alias.unwrap_or_default().to_string();
+
+    service.add_alias(&name, &alias).context(Storage)?;
+    service.write_to_file().context(Storage)?;
+
+    return Ok(format!("{} can now be referred to as {}", name, alias))
+}
+
+pub fn handle_grouping(service: &ProjectDataService, project_name: Option<&str>, grouping: Option<&str>) -> Result<String, AppError> {
+    let name = project_name.unwrap_or_default().to_string();
+    let grouping = parse_grouping(grouping.unwrap_or_default())?;
+    let line = match grouping {
+        LineGrouping::Task => "one line per task",
+        LineGrouping::Day => "one line per day",
+        LineGrouping::Week => "one line per week",
+        LineGrouping::Entry => "one line per entry",
+        LineGrouping::Single => "a single line"
+    };
+
+    service.set_grouping(&name, grouping).context(Storage)?;
+    service.write_to_file().context(Storage)?;
+
+    return Ok(format!("Invoices for {} have {}", name, line))
+}
+
+fn parse_grouping(grouping: &str) -> Result<LineGrouping, AppError> {
+    return match grouping {
+        "task" => Ok(LineGrouping::Task),
+        "day" => Ok(LineGrouping::Day),
+        "week" => Ok(LineGrouping::Week),
+        "entry" => Ok(LineGrouping::Entry),
+        "single" => Ok(LineGrouping::Single),
+        g => Validation {
+            message: format!("Invalid grouping {}, expected task, day, week, entry or single", g)
+        }.fail()
+    }
+}
+
+fn parse_rate(rate: &str) -> Result<Money, AppError> {
+    return rate.parse::<Money>().ok().ok_or(AppError::Validation {
+        message: format!("Invalid rate {}, expected a whole number", rate)
+    })
+}
+
+fn parse_unit(unit: Option<&str>) -> Result<BillableUnit, AppError> {
+    return match unit {
+        None | Some("hour") => Ok(BillableUnit::Hour),
+        Some("day") => Ok(BillableUnit::Day),
+        Some(u) => Validation {
+            message: format!("Invalid unit {}, expected hour or day", u)
+        }.fail()
+    }
+}
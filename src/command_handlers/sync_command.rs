@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use chrono::Local;
+use snafu::{OptionExt, ResultExt};
+
+use crate::domain::errors::app::{AppError, Api, NotSubmitted, Storage, Validation};
+use crate::domain::errors::project_data::ProjectDataError;
+use crate::domain::objects::Config;
+use crate::domain::objects::invoice::Submission;
+use crate::services::file_database_service::ProjectDataService;
+use crate::services::lex_office_service::LexOfficeService;
+
+
+/// Creates the invoices waiting in the queue in LexOffice, oldest first. Fails when any of them was not created.
+pub fn handle(config: &Config, service: &ProjectDataService) -> Result<String, AppError> {
+    let queued = service.get_queued_invoices().context(Storage)?;
+    if queued.is_empty() {
+        return Ok("Nothing to submit".to_string())
+    }
+
+    let lex_office = LexOfficeService::new(config).context(Api)?;
+    let mut lines = vec![];
+    let mut failed = false;
+    for invoice in queued.iter() {
+        match submit(service, &lex_office, &invoice.number) {
+            Ok(line) => lines.push(line),
+            Err(e) => {
+                failed = true;
+                lines.push(e.to_string());
+            }
+        }
+    }
+
+    if failed {
+        return NotSubmitted { message: lines.join("\n") }.fail()
+    }
+    return Ok(lines.join("\n"))
+}
+
+/// Submission of an invoice that is queued as of now.
+pub fn queue() -> Submission {
+    return Submission {
+        queued: Local::now().format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+        attempts: 0,
+        last_error: None,
+        rejected: false
+    }
+}
+
+/// Creates the queued invoice `number` in LexOffice, cancellations as credit notes of the invoices they cancel.
+/// The attempt is saved before it is made, so an invoice that may have reached LexOffice is looked up there instead
+/// of being created twice. Invoices that fail because LexOffice is unreachable or busy stay queued,
+/// invoices LexOffice rejects leave the queue.
+pub fn submit(service: &ProjectDataService, lex_office: &LexOfficeService, number: &String) -> Result<String, AppError> {
+    let invoice = service.get_invoice(number).context(Storage)?;
+    let cancelled_id = match &invoice.cancels {
+        Some(cancelled) => Some(service.get_invoice(cancelled).context(Storage)?.lex_office_id.context(Validation {
+            message: format!("Invoice {} is not in LexOffice, its cancellation {} can not be created there", cancelled, number)
+        })?),
+        None => None
+    };
+    let kind = if cancelled_id.is_some() { "credit note" } else { "invoice" };
+    let known: HashSet<String> = service.get_invoices().context(Storage)?.into_iter().filter_map(|i| i.lex_office_id).collect();
+    let submission = service.record_submission_attempt(number).context(Storage)?;
+    service.write_progress_to_file().context(Storage)?;
+
+    let create = || match &cancelled_id {
+        Some(cancelled_id) => lex_office.create_credit_note(cancelled_id, &invoice),
+        None => lex_office.create_invoice(&invoice)
+    };
+    let created = match submission.attempts {
+        0 => create(),
+        _ => match &cancelled_id {
+            Some(_) => lex_office.find_credit_note(&invoice, &known),
+            None => lex_office.find_invoice(&invoice, &known)
+        }.and_then(|found| match found {
+            Some(voucher) => Ok(voucher),
+            None => create()
+        })
+    };
+    let voucher = match created {
+        Ok(voucher) => voucher,
+        Err(e) => {
+            service.record_submission_failure(number, &e.to_string(), e.is_rejection()).context(Storage)?;
+            service.write_progress_to_file().context(Storage)?;
+            let message = if e.is_rejection() && cancelled_id.is_some() {
+                format!("LexOffice rejected credit note {}: {}\nIt is no longer queued, create it in LexOffice by hand", number, e)
+            } else if e.is_rejection() {
+                format!("LexOffice rejected invoice {}: {}\nIt is no longer queued, cancel it with `hours invoice cancel {}`", number, e, number)
+            } else if e.is_transient() {
+                format!("Queued {} {}, LexOffice is not available: {}\nSend it later with `hours sync`", kind, number, e)
+            } else {
+                format!("Could not create {} {} in LexOffice: {}\nIt stays queued, send it with `hours sync`", kind, number, e)
+            };
+            return NotSubmitted { message }.fail()
+        }
+    };
+
+    service.complete_submission(number, &voucher.id, voucher.number.as_ref()).context(Storage)?;
+    // Kept from being issued by hours as well, in case the number pattern matches LexOffice's
+    if let Some(voucher_number) = &voucher.number {
+        match service.record_invoice_number(voucher_number) {
+            Ok(()) | Err(ProjectDataError::InvoiceNumberUsed { .. }) => (),
+            Err(e) => return Err(e).context(Storage)
+        }
+    }
+    service.write_progress_to_file().context(Storage)?;
+
+    return Ok(format!("Created {} {} in LexOffice as {}", kind, number, voucher.number.unwrap_or(voucher.id)))
+}
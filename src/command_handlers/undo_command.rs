@@ -0,0 +1,39 @@
+use snafu::ResultExt;
+
+use crate::command_handlers::history_command::format_event;
+use crate::domain::errors::app::{AppError, Storage};
+use crate::domain::objects::audit::AuditEvent;
+use crate::services::file_database_service::ProjectDataService;
+
+
+pub fn handle(service: &ProjectDataService, list: bool) -> Result<String, AppError> {
+    if list {
+        let operations = service.undoable().context(Storage)?;
+        if operations.is_empty() {
+            return Ok("Nothing to undo".to_string())
+        }
+
+        let lines: Vec<String> = operations.iter()
+            .flatten()
+            .map(format_event)
+            .collect();
+
+        return Ok(lines.join("\n"))
+    }
+
+    let events = match service.undo().context(Storage)? {
+        Some(events) => events,
+        None => return Ok("Nothing to undo".to_string())
+    };
+    service.write_to_file().context(Storage)?;
+
+    return Ok(format!("Undid {}", describe(&events)))
+}
+
+fn describe(events: &[AuditEvent]) -> String {
+    let operations: Vec<String> = events.iter()
+        .map(|e| format!("{} {}", e.operation, e.target))
+        .collect();
+
+    return operations.join(", ")
+}
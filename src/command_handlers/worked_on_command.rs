@@ -0,0 +1,63 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use snafu::{OptionExt, ResultExt};
+
+use crate::domain::errors::app::{AppError, Storage, Validation};
+use crate::domain::errors::project_data::ProjectDataError;
+use crate::domain::objects::project_data::Quantity;
+use crate::services::date_service::{DateService, ACCEPTED_FORMATS};
+use crate::services::file_database_service::ProjectDataService;
+
+
+pub fn handle(service: &ProjectDataService, task: Option<&str>, time: Option<&str>, date: Option<&str>, new_task: bool, force: bool) -> Result<String, AppError> {
+    let (tsk, tme) = match (task, time) {
+        (Some(tsk), Some(tme)) => (tsk.to_string(), tme),
+        _ => return Ok(String::new())
+    };
+
+    let quantity = tme.parse::<Quantity>().ok().context(Validation {
+        message: format!("Invalid time {}, expected a number", tme)
+    })?;
+    let timestamp = match date {
+        Some(d) => Some(parse_date(d, force)?),
+        None => None
+    };
+    let project_id = service.get_selected_project_id().context(Storage)?.context(Validation {
+        message: "No project selected, run `hours for <project>` first"
+    })?;
+
+    let task = match service.resolve_task(&project_id, &tsk, new_task) {
+        Err(ProjectDataError::UnknownTask { suggestions, .. }) => return Validation {
+            message: format!("Unknown task {}{}\nGive --new to add it as a new task", tsk, suggestions)
+        }.fail(),
+        result => result.context(Storage)?
+    };
+    if new_task {
+        service.add_task(&project_id, &task).context(Storage)?;
+    }
+    service.add_billable_entry(&project_id, &task, quantity, timestamp).context(Storage)?;
+    service.write_to_file().context(Storage)?;
+
+    return Ok(format!("Marked {} to {}", tme, task))
+}
+
+fn parse_date(input: &str, force: bool) -> Result<DateTime<Local>, AppError> {
+    let now = Local::now();
+    let date = DateService::parse(input, now.date().naive_local()).context(Validation {
+        message: format!("Invalid date {}, expected one of {}", input, ACCEPTED_FORMATS)
+    })?;
+
+    if date > now.date().naive_local() && !force {
+        return Validation {
+            message: format!("Date {} lies in the future, give --force to use it anyway", date)
+        }.fail()
+    }
+
+    return Ok(at_current_time(date, now))
+}
+
+fn at_current_time(date: NaiveDate, now: DateTime<Local>) -> DateTime<Local> {
+    // The current time can fall into a daylight saving gap on the given date, noon never does
+    return Local.from_local_datetime(&date.and_time(now.time())).earliest()
+        .or_else(|| Local.from_local_datetime(&date.and_hms(12, 0, 0)).earliest())
+        .unwrap_or(now);
+}
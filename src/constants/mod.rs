@@ -1,3 +1,26 @@
 pub const HOME_ENV_KEY: &str = "HOME";
 pub const CONFIG_FILE_PATH: &str = "~/.hou-rs/config";
-pub const PROJECTS_DATA_FILE: &str = "~/.hou-rs/data/projects";
\ No newline at end of file
+pub const PROJECTS_DATA_FILE: &str = "~/.hou-rs/data/projects";
+pub const PROJECTS_SQLITE_FILE: &str = "~/.hou-rs/data/projects.sqlite";
+pub const AUDIT_LOG_FILE: &str = "~/.hou-rs/data/audit.jsonl";
+pub const USER_ENV_KEY: &str = "USER";
+pub const BACKUP_DIRECTORY: &str = "~/.hou-rs/data/backups";
+pub const TEMPLATE_DIRECTORY: &str = "~/.hou-rs/templates";
+pub const DEFAULT_BACKUP_COUNT: usize = 10;
+pub const DATABASE_LOCK_TIMEOUT_SECS: u64 = 5;
+pub const DEFAULT_TAX_RATE: f32 = 19.0;
+pub const CURRENCY: &str = "EUR";
+pub const LEX_OFFICE_API_URL: &str = "https://api.lexoffice.io/v1";
+pub const LEX_OFFICE_TIMEOUT_SECS: u64 = 30;
+pub const LEX_OFFICE_RETRIES: u32 = 3;
+pub const LEX_OFFICE_BACKOFF_MILLIS: u64 = 500;
+pub const DEFAULT_INVOICE_NUMBER_PATTERN: &str = "RE-{YYYY}-{seq:04}";
+/// Start of the references of invoices that LexOffice numbers, they are outside of the invoice number sequence
+pub const LEX_OFFICE_REFERENCE_PREFIX: &str = "LX";
+
+// Exit codes follow sysexits(3) so scripts can tell failures apart.
+pub const EXIT_USAGE: i32 = 64;
+pub const EXIT_DATA: i32 = 65;
+pub const EXIT_UNAVAILABLE: i32 = 69;
+pub const EXIT_STORAGE: i32 = 74;
+pub const EXIT_CONFIG: i32 = 78;
@@ -0,0 +1,114 @@
+use snafu::Snafu;
+
+use crate::constants::{EXIT_CONFIG, EXIT_DATA, EXIT_STORAGE, EXIT_UNAVAILABLE, EXIT_USAGE};
+use crate::domain::errors::config_builder::ConfigBuilderError;
+use crate::domain::errors::lex_office::LexOfficeError;
+use crate::domain::errors::project_data::ProjectDataError;
+use crate::domain::errors::template::TemplateError;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum AppError {
+    #[snafu(display("{}", source))]
+    Config {
+        source: ConfigBuilderError,
+    },
+    #[snafu(display("{}", source))]
+    Storage {
+        source: ProjectDataError,
+    },
+    #[snafu(display("{}", message))]
+    Validation {
+        message: String,
+    },
+    #[snafu(display("{}", message))]
+    Usage {
+        message: String,
+    },
+    #[snafu(display("{}", source))]
+    Template {
+        source: TemplateError,
+    },
+    #[snafu(display("Could not write invoice {}: {}", path, source))]
+    WriteInvoice {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("{}", source))]
+    Api {
+        source: LexOfficeError,
+    },
+    #[snafu(display("Could not read input: {}", source))]
+    ReadInput {
+        source: std::io::Error,
+    },
+    #[snafu(display("{}", message))]
+    NotSubmitted {
+        message: String,
+    },
+}
+
+impl AppError {
+    pub fn exit_code(&self) -> i32 {
+        return match self {
+            AppError::Config { .. } => EXIT_CONFIG,
+            AppError::Storage { source: ProjectDataError::UnknownProject { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::AmbiguousProject { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::UnknownTask { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::AmbiguousTask { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::ProjectExists { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::AliasTaken { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::InvoiceNumberUsed { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::UnknownInvoice { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::NotCancellable { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::NotPayable { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::NotQueued { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::EmptyBackup { .. } } => EXIT_DATA,
+            AppError::Storage { source: ProjectDataError::InvalidNumberPattern { .. } } => EXIT_CONFIG,
+            AppError::Storage { .. } => EXIT_STORAGE,
+            AppError::Validation { .. } => EXIT_DATA,
+            AppError::Usage { .. } => EXIT_USAGE,
+            AppError::Template { source: TemplateError::ReadTemplate { .. } } => EXIT_STORAGE,
+            AppError::Template { .. } => EXIT_DATA,
+            AppError::WriteInvoice { .. } => EXIT_STORAGE,
+            AppError::Api { source: LexOfficeError::MissingApiKey } => EXIT_CONFIG,
+            AppError::Api { .. } => EXIT_UNAVAILABLE,
+            AppError::NotSubmitted { .. } => EXIT_UNAVAILABLE,
+            AppError::ReadInput { .. } => EXIT_USAGE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::domain::objects::suggestions::Suggestions;
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let config = AppError::Config { source: ConfigBuilderError::NoneError };
+        let storage = AppError::Storage {
+            source: ProjectDataError::CreateDataDirectory {
+                path: "/".to_string(),
+                source: std::io::Error::from(std::io::ErrorKind::PermissionDenied)
+            }
+        };
+        let validation = AppError::Validation { message: "invalid".to_string() };
+        let api = AppError::Api { source: LexOfficeError::UnexpectedResponse { path: "/invoices".to_string(), message: "timeout".to_string() } };
+
+        let codes: HashSet<i32> = vec![config.exit_code(), storage.exit_code(), validation.exit_code(), api.exit_code()]
+            .into_iter()
+            .collect();
+
+        assert_eq!(codes.len(), 4);
+        assert_eq!(codes.contains(&0), false);
+    }
+
+    #[test]
+    fn test_unknown_project_is_data_error() {
+        let error = AppError::Storage { source: ProjectDataError::UnknownProject { project: "foo".to_string(), suggestions: Suggestions::default() } };
+
+        assert_eq!(error.exit_code(), EXIT_DATA);
+    }
+}
@@ -1,8 +1,14 @@
 use snafu::{self, Snafu};
 
+use crate::domain::errors::toml_file::FileError;
+
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub(crate)))]
 pub enum ConfigBuilderError {
     #[snafu(display("Could not build config, did you remember to call correct builder methods?"))]
     NoneError,
-}
\ No newline at end of file
+    #[snafu(display("Could not load config: {}", source))]
+    ReadConfig {
+        source: FileError,
+    },
+}
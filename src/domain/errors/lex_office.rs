@@ -0,0 +1,38 @@
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum LexOfficeError {
+    #[snafu(display("Set lex_office_api_key in the config to use LexOffice"))]
+    MissingApiKey,
+    #[snafu(display("LexOffice request failed: {}", source))]
+    Request {
+        source: Box<ureq::Error>,
+    },
+    #[snafu(display("Unexpected response from LexOffice to {}: {}", path, message))]
+    UnexpectedResponse {
+        path: String,
+        message: String,
+    },
+}
+
+impl LexOfficeError {
+    /// Whether the request may succeed later: LexOffice was not reachable, too busy or failed itself.
+    pub fn is_transient(&self) -> bool {
+        return match self {
+            LexOfficeError::Request { source } => match source.as_ref() {
+                ureq::Error::Status(status, _) => *status == 429 || *status >= 500,
+                ureq::Error::Transport(_) => true
+            },
+            _ => false
+        }
+    }
+
+    /// Whether LexOffice refused the request as invalid, sending it again fails the same way.
+    pub fn is_rejection(&self) -> bool {
+        return match self {
+            LexOfficeError::Request { source } => matches!(source.as_ref(), ureq::Error::Status(400 | 406 | 422, _)),
+            _ => false
+        }
+    }
+}
@@ -1,2 +1,6 @@
 pub mod toml_file;
-pub mod config_builder;
\ No newline at end of file
+pub mod config_builder;
+pub mod project_data;
+pub mod app;
+pub mod template;
+pub mod lex_office;
@@ -0,0 +1,174 @@
+use snafu::Snafu;
+use rustbreak::RustbreakError;
+
+use crate::domain::errors::toml_file::FileError;
+use crate::domain::objects::suggestions::Suggestions;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum ProjectDataError {
+    #[snafu(display("Could not resolve data file path: {}", source))]
+    ResolvePath {
+        source: FileError,
+    },
+    #[snafu(display("Could not create data directory {}: {}", path, source))]
+    CreateDataDirectory {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not open lock file {}: {}", path, source))]
+    LockFile {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("{} is in use by another hours process, try again once it has finished", path))]
+    Locked {
+        path: String,
+    },
+    #[snafu(display("Could not access project database: {}", source))]
+    Database {
+        source: RustbreakError,
+    },
+    #[snafu(display("Could not read data file {}: {}", path, source))]
+    ReadDataFile {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not write data file {}: {}", path, source))]
+    WriteDataFile {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not encode project data: {}", source))]
+    Encode {
+        source: rustbreak::DeSerError,
+    },
+    #[snafu(display("Could not parse data file {}: {}, fix it or restore a backup (see `hours db backup --list`)", path, source))]
+    DecodeDataFile {
+        path: String,
+        source: rustbreak::DeSerError,
+    },
+    #[snafu(display("{} has schema version {}, this version of hours only reads up to version {}", path, version, supported))]
+    UnsupportedSchemaVersion {
+        path: String,
+        version: u64,
+        supported: u32,
+    },
+    #[snafu(display("Could not migrate {} to schema version {}: {}", path, version, source))]
+    MigrateSchema {
+        path: String,
+        version: u32,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Could not detect the encoding of {}, it is not valid YAML, RON or bincode", path))]
+    UnknownEncoding {
+        path: String,
+    },
+    #[snafu(display("Converting {} to {} did not preserve all data, the original file was kept", path, encoding))]
+    LossyConversion {
+        path: String,
+        encoding: String,
+    },
+    #[snafu(display("Could not access SQLite database: {}", source))]
+    Sqlite {
+        source: rusqlite::Error,
+    },
+    #[snafu(display("{} already exists, remove it first to migrate into it", path))]
+    TargetExists {
+        path: String,
+    },
+    #[snafu(display("No backup called {}, see `hours db backup --list`", snapshot))]
+    UnknownBackup {
+        snapshot: String,
+    },
+    #[snafu(display("{} is not a valid backup, nothing was restored", path))]
+    InvalidBackup {
+        path: String,
+    },
+    #[snafu(display("Backup {} holds no projects or entries, give --force to restore it anyway", path))]
+    EmptyBackup {
+        path: String,
+    },
+    #[snafu(display("{} is not an hours database: {}", path, reason))]
+    NotADatabase {
+        path: String,
+        reason: String,
+    },
+    #[snafu(display("Could not serialize audit event: {}", source))]
+    SerializeAuditEvent {
+        source: serde_json::Error,
+    },
+    #[snafu(display("Invalid audit log entry on line {}: {}", line, source))]
+    AuditLogEntry {
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Could not replay audit event from {}: {}", timestamp, source))]
+    ReplayEvent {
+        timestamp: String,
+        source: serde_json::Error,
+    },
+    #[snafu(display("The audit log holds no snapshot of the data to start from, replaying it would lose everything saved before it"))]
+    NoSnapshot,
+    #[snafu(display("Audit event from {} refers to an entry without id, it can not be replayed unambiguously", timestamp))]
+    LegacyAuditEvent {
+        timestamp: String,
+    },
+    #[snafu(display("Unknown project {}{}", project, suggestions))]
+    UnknownProject {
+        project: String,
+        suggestions: Suggestions,
+    },
+    #[snafu(display("Project {} is ambiguous, it could be {}", project, candidates.join(", ")))]
+    AmbiguousProject {
+        project: String,
+        candidates: Vec<String>,
+    },
+    #[snafu(display("Project {} already exists", project))]
+    ProjectExists {
+        project: String,
+    },
+    #[snafu(display("Alias {} already refers to project {}", alias, project))]
+    AliasTaken {
+        alias: String,
+        project: String,
+    },
+    #[snafu(display("Unknown task {} for project {}{}", task, project, suggestions))]
+    UnknownTask {
+        project: String,
+        task: String,
+        suggestions: Suggestions,
+    },
+    #[snafu(display("Invalid invoice number pattern {}: {}", pattern, reason))]
+    InvalidNumberPattern {
+        pattern: String,
+        reason: String,
+    },
+    #[snafu(display("Invoice number {} is already in use", number))]
+    InvoiceNumberUsed {
+        number: String,
+    },
+    #[snafu(display("Unknown invoice {}, see `hours invoices list`", number))]
+    UnknownInvoice {
+        number: String,
+    },
+    #[snafu(display("Invoice {} can not be cancelled, {}", number, reason))]
+    NotCancellable {
+        number: String,
+        reason: String,
+    },
+    #[snafu(display("Invoice {} can not be marked as paid, {}", number, reason))]
+    NotPayable {
+        number: String,
+        reason: String,
+    },
+    #[snafu(display("Invoice {} is not waiting to be created in LexOffice", number))]
+    NotQueued {
+        number: String,
+    },
+    #[snafu(display("Task {} is ambiguous, it could be {}", task, candidates.join(", ")))]
+    AmbiguousTask {
+        task: String,
+        candidates: Vec<String>,
+    },
+}
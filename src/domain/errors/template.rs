@@ -0,0 +1,32 @@
+use snafu::Snafu;
+
+use crate::domain::errors::toml_file::FileError;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum TemplateError {
+    #[snafu(display("Could not resolve template directory: {}", source))]
+    ResolveTemplateDirectory {
+        source: FileError,
+    },
+    #[snafu(display("Could not read template {}: {}", path, source))]
+    ReadTemplate {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("No template called {}, available templates are {}", name, available.join(", ")))]
+    UnknownTemplate {
+        name: String,
+        available: Vec<String>,
+    },
+    #[snafu(display("Invalid template {}: {}", name, source))]
+    ParseTemplate {
+        name: String,
+        source: Box<handlebars::TemplateError>,
+    },
+    #[snafu(display("Could not render template {}: {}", name, source))]
+    RenderTemplate {
+        name: String,
+        source: Box<handlebars::RenderError>,
+    },
+}
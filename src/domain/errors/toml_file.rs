@@ -27,6 +27,8 @@ pub enum FileError {
     #[snafu(display("Could not deserialize file {}: {}", path, source))]
     DeserializeToml {
         path: String,
+        line: Option<usize>,
+        column: Option<usize>,
         source: toml::de::Error,
     },
 }
\ No newline at end of file
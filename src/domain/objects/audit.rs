@@ -0,0 +1,96 @@
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum Operation {
+    #[serde(rename = "add_project")]
+    AddProject,
+    #[serde(rename = "update_project")]
+    UpdateProject,
+    #[serde(rename = "add_task")]
+    AddTask,
+    #[serde(rename = "add_billable_entry")]
+    AddBillableEntry,
+    #[serde(rename = "remove_billable_entry")]
+    RemoveBillableEntry,
+    #[serde(rename = "select_project")]
+    SelectProject,
+    #[serde(rename = "restore")]
+    Restore,
+    #[serde(rename = "issue_invoice_number")]
+    IssueInvoiceNumber,
+    #[serde(rename = "add_invoice")]
+    AddInvoice,
+    #[serde(rename = "update_invoice")]
+    UpdateInvoice
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operation::AddProject => "add_project",
+            Operation::UpdateProject => "update_project",
+            Operation::AddTask => "add_task",
+            Operation::AddBillableEntry => "add_billable_entry",
+            Operation::RemoveBillableEntry => "remove_billable_entry",
+            Operation::SelectProject => "select_project",
+            Operation::Restore => "restore",
+            Operation::IssueInvoiceNumber => "issue_invoice_number",
+            Operation::AddInvoice => "add_invoice",
+            Operation::UpdateInvoice => "update_invoice"
+        };
+
+        return write!(f, "{}", name)
+    }
+}
+
+impl Operation {
+    /// Operations that can not be reverted, undo stops at them. Invoice numbers must never be handed out twice,
+    /// and issued invoices must not disappear.
+    pub fn blocks_undo(&self) -> bool {
+        return match self {
+            Operation::AddProject | Operation::UpdateProject | Operation::AddTask | Operation::AddBillableEntry | Operation::RemoveBillableEntry
+            | Operation::SelectProject => false,
+            Operation::Restore | Operation::IssueInvoiceNumber | Operation::AddInvoice | Operation::UpdateInvoice => true
+        }
+    }
+}
+
+/// One change to `ProjectData`. `before` and `after` hold the changed item, `None` when it did not exist.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AuditEvent {
+    pub timestamp: String,
+    pub user: Option<String>,
+    pub operation: Operation,
+    pub target: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    /// Identifies the changes saved together by one command
+    #[serde(default)]
+    pub batch: Option<String>,
+    /// Batch that this event reverts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undoes: Option<String>
+}
+
+impl AuditEvent {
+    /// Events without a batch were saved before batches existed, each of them is its own batch.
+    pub fn batch_id(&self) -> &String {
+        return self.batch.as_ref().unwrap_or(&self.timestamp);
+    }
+
+    /// Whether the event adds or removes an entry logged before entries had ids, such entries are only found by their content.
+    pub fn refers_to_legacy_entry(&self) -> bool {
+        let legacy = |entry: &Option<Value>| match entry {
+            Some(entry) => entry.get("id").and_then(Value::as_u64).unwrap_or(0) == 0,
+            None => false
+        };
+
+        return match self.operation {
+            Operation::AddBillableEntry | Operation::RemoveBillableEntry => legacy(&self.before) || legacy(&self.after),
+            _ => false
+        }
+    }
+}
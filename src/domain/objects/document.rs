@@ -0,0 +1,67 @@
+use serde::Serialize;
+
+use crate::domain::objects::{BankDetails, Sender};
+use crate::domain::objects::invoice::ServicePeriod;
+use crate::domain::objects::project_data::{BillableUnit, Quantity};
+
+// Data invoice and timesheet templates are rendered with. Field names are what templates refer to, so they must not
+// change. Amounts are formatted like `1234.50 EUR`, the `_cents` fields hold the same amounts as numbers.
+
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct InvoiceDocument {
+    /// Invoice number, e.g. `RE-2020-0001`
+    pub number: String,
+    /// `[sender]` section of the config, missing when it is not configured
+    pub sender: Option<Sender>,
+    /// `[bank]` section of the config, missing when it is not configured
+    pub bank: Option<BankDetails>,
+    pub project: ProjectDocument,
+    /// Date of the invoice, `YYYY-MM-DD`
+    pub date: String,
+    /// First and last day of the billed work, missing for invoices without entries
+    pub service_period: Option<ServicePeriod>,
+    pub currency: &'static str,
+    /// Line items grouped by the project's grouping
+    pub line_items: Vec<LineItemDocument>,
+    /// All billed entries oldest first, for timesheets
+    pub entries: Vec<EntryDocument>,
+    /// Sum of the quantities of all entries
+    pub total_quantity: Quantity,
+    /// VAT rate in percent, e.g. `19`
+    pub tax_rate: String,
+    pub net: String,
+    pub net_cents: i64,
+    pub tax: String,
+    pub tax_cents: i64,
+    pub gross: String,
+    pub gross_cents: i64
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ProjectDocument {
+    pub id: String,
+    pub name: String,
+    pub unit: BillableUnit,
+    pub unit_price: String,
+    /// Customer address, one line per entry
+    pub address: Vec<String>
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct LineItemDocument {
+    pub description: String,
+    pub quantity: Quantity,
+    pub unit: BillableUnit,
+    pub unit_price: String,
+    pub net: String,
+    pub net_cents: i64
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct EntryDocument {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub task: String,
+    pub quantity: Quantity
+}
@@ -0,0 +1,55 @@
+use std::fmt;
+
+use crate::domain::objects::project_data::{BillableEntry, Quantity};
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    UnknownProject { project_id: String },
+    UnknownTask { project_id: String, task: String },
+    InvalidDate { date: String },
+    NegativeQuantity { quantity: Quantity },
+    DuplicateEntry { entry: BillableEntry },
+    UnknownSelectedProject { project_id: String }
+}
+
+impl Problem {
+    /// Problems that can be repaired without losing or guessing data.
+    pub fn is_fixable(&self) -> bool {
+        return match self {
+            Problem::UnknownTask { .. } | Problem::UnknownSelectedProject { .. } => true,
+            Problem::UnknownProject { .. } | Problem::InvalidDate { .. } | Problem::NegativeQuantity { .. } | Problem::DuplicateEntry { .. } => false
+        }
+    }
+
+    /// Problems that can be repaired by deleting data, which may have been recorded like this on purpose.
+    pub fn needs_confirmation(&self) -> bool {
+        return matches!(self, Problem::DuplicateEntry { .. });
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Problem::UnknownProject { project_id } => write!(f, "entry of unknown project {}", project_id),
+            Problem::UnknownTask { project_id, task } => write!(f, "task {} is missing from project {}", task, project_id),
+            Problem::InvalidDate { date } => write!(f, "invalid date {}", date),
+            Problem::NegativeQuantity { quantity } => write!(f, "negative quantity {}", quantity),
+            Problem::DuplicateEntry { .. } => write!(f, "duplicate of an earlier entry"),
+            Problem::UnknownSelectedProject { project_id } => write!(f, "selected project {} does not exist", project_id)
+        }
+    }
+}
+
+/// A problem in the project data and where it was found, e.g. `billable[3]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub location: String,
+    pub problem: Problem
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}: {}", self.location, self.problem)
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+
+use crate::constants::CURRENCY;
+use crate::domain::objects::{BankDetails, Sender};
+use crate::domain::objects::project_data::{BillableUnit, Money, Quantity};
+
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum InvoiceStatus {
+    #[serde(rename = "draft")]
+    Draft,
+    #[serde(rename = "sent")]
+    Sent,
+    #[serde(rename = "paid")]
+    Paid,
+    #[serde(rename = "cancelled")]
+    Cancelled
+}
+
+impl fmt::Display for InvoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            InvoiceStatus::Draft => "draft",
+            InvoiceStatus::Sent => "sent",
+            InvoiceStatus::Paid => "paid",
+            InvoiceStatus::Cancelled => "cancelled"
+        };
+
+        return write!(f, "{}", name)
+    }
+}
+
+/// First and last day of the billed work, `YYYY-MM-DD`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ServicePeriod {
+    pub start: String,
+    pub end: String
+}
+
+/// Creation of an invoice in LexOffice that LexOffice has not confirmed yet. Attempts are recorded before they are
+/// made, so that after an interrupted attempt the invoice is looked up in LexOffice instead of being created twice.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Submission {
+    /// When the invoice was queued for submission
+    pub queued: String,
+    pub attempts: u32,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+    /// LexOffice refused the invoice, it is not submitted again
+    #[serde(default)]
+    pub rejected: bool
+}
+
+/// An issued invoice. Amounts are in cents and fixed when the invoice is issued.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Invoice {
+    pub number: String,
+    /// Number of the invoice this one cancels, `None` for regular invoices
+    #[serde(default)]
+    pub cancels: Option<String>,
+    pub project_id: String,
+    /// Customer address at the time the invoice was issued
+    pub customer: Vec<String>,
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub service_period: Option<ServicePeriod>,
+    pub line_items: Vec<LineItem>,
+    pub net: i64,
+    /// VAT rate in percent
+    pub tax_rate: f32,
+    pub tax: i64,
+    pub gross: i64,
+    pub status: InvoiceStatus,
+    pub lex_office_id: Option<String>,
+    /// Number LexOffice gave the invoice, the one the customer gets. `number` is then only the reference within hours
+    #[serde(default)]
+    pub lex_office_number: Option<String>,
+    /// Set while the invoice waits to be created in LexOffice
+    #[serde(default)]
+    pub submission: Option<Submission>,
+    /// Ids of the billable entries the invoice covers
+    pub entries: Vec<u64>
+}
+
+impl Invoice {
+    /// Entries of invoices that were not cancelled are billed.
+    pub fn bills(&self, entry_id: u64) -> bool {
+        return self.status != InvoiceStatus::Cancelled && self.entries.contains(&entry_id);
+    }
+}
+
+/// One row of an invoice, all entries of a task added up.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LineItem {
+    pub description: String,
+    pub quantity: Quantity,
+    pub unit: BillableUnit,
+    pub unit_price: Money
+}
+
+impl LineItem {
+    /// Net amount in cents
+    pub fn net(&self) -> i64 {
+        return (self.quantity as f64 * self.unit_price as f64 * 100.0).round() as i64;
+    }
+}
+
+/// Everything printed on an invoice, before it is rendered or submitted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvoiceDraft {
+    pub number: String,
+    /// Number of the cancelled invoice when this is a cancellation
+    pub cancels: Option<String>,
+    pub sender: Sender,
+    pub bank: BankDetails,
+    pub customer: Vec<String>,
+    pub date: NaiveDate,
+    pub service_period: Option<ServicePeriod>,
+    pub line_items: Vec<LineItem>,
+    /// VAT rate in percent
+    pub tax_rate: f32
+}
+
+// Amounts are in cents
+impl InvoiceDraft {
+    pub fn net(&self) -> i64 {
+        return self.line_items.iter().map(LineItem::net).sum();
+    }
+
+    pub fn tax(&self) -> i64 {
+        return tax_of(self.net(), self.tax_rate);
+    }
+
+    pub fn gross(&self) -> i64 {
+        return self.net() + self.tax();
+    }
+}
+
+/// VAT in cents on `net` cents, rounded to whole cents.
+pub fn tax_of(net: i64, tax_rate: f32) -> i64 {
+    return (net as f64 * tax_rate as f64 / 100.0).round() as i64;
+}
+
+/// Formats an amount in cents, e.g. `1234.50 EUR`.
+pub fn format_amount(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+
+    return format!("{}{}.{:02} {}", sign, cents.abs() / 100, cents.abs() % 100, CURRENCY);
+}
@@ -0,0 +1,227 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Serialize, Deserialize};
+
+use crate::domain::objects::invoice::{InvoiceStatus, LineItem, ServicePeriod, Submission};
+use crate::domain::objects::project_data::{BillableEntry, BillableUnit, InvoiceSequence, Money, Project, Quantity};
+
+// Frozen layouts of earlier schema versions. RON and bincode files can only be read with the exact layout they were
+// written in, YAML files are migrated without them.
+
+
+/// Billable entry of schema versions 0 to 4, before entry ids.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BillableEntryV4 {
+    pub project_id: String,
+    pub task: String,
+    pub quantity: Quantity,
+    pub date: String
+}
+
+/// Project of schema versions 0 and 1, before aliases.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectV1 {
+    pub name: String,
+    pub unit_price: Money,
+    pub unit: BillableUnit,
+    pub tasks: HashSet<String>
+}
+
+/// Project data of schema version 0, and the data of the version 1 envelope.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectDataV1 {
+    pub billable: Vec<BillableEntryV4>,
+    pub projects: HashMap<String, ProjectV1>,
+    #[serde(default)]
+    pub selected_project: Option<String>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VersionedProjectDataV1 {
+    pub version: u32,
+    pub data: ProjectDataV1
+}
+
+/// Project of schema version 2, before customer addresses.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectV2 {
+    pub name: String,
+    pub unit_price: Money,
+    pub unit: BillableUnit,
+    pub tasks: HashSet<String>,
+    pub aliases: Vec<String>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectDataV2 {
+    pub billable: Vec<BillableEntryV4>,
+    pub projects: HashMap<String, ProjectV2>,
+    pub selected_project: Option<String>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VersionedProjectDataV2 {
+    pub version: u32,
+    pub data: ProjectDataV2
+}
+
+/// Project of schema versions 3 to 6, before line grouping.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectV6 {
+    pub name: String,
+    pub unit_price: Money,
+    pub unit: BillableUnit,
+    pub tasks: HashSet<String>,
+    pub aliases: Vec<String>,
+    pub address: Vec<String>
+}
+
+/// Project data of schema version 3, before invoice numbers.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectDataV3 {
+    pub billable: Vec<BillableEntryV4>,
+    pub projects: HashMap<String, ProjectV6>,
+    pub selected_project: Option<String>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VersionedProjectDataV3 {
+    pub version: u32,
+    pub data: ProjectDataV3
+}
+
+/// Project data of schema version 4, before entry ids and invoices.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectDataV4 {
+    pub billable: Vec<BillableEntryV4>,
+    pub projects: HashMap<String, ProjectV6>,
+    pub selected_project: Option<String>,
+    pub invoice_numbers: InvoiceSequence
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VersionedProjectDataV4 {
+    pub version: u32,
+    pub data: ProjectDataV4
+}
+
+/// Invoice of schema version 5, before cancellations.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InvoiceV5 {
+    pub number: String,
+    pub project_id: String,
+    pub customer: Vec<String>,
+    pub date: String,
+    pub service_period: Option<ServicePeriod>,
+    pub line_items: Vec<LineItem>,
+    pub net: i64,
+    pub tax_rate: f32,
+    pub tax: i64,
+    pub gross: i64,
+    pub status: InvoiceStatus,
+    pub lex_office_id: Option<String>,
+    pub entries: Vec<u64>
+}
+
+/// Project data of schema version 5, before cancellations. `BillableEntry` has not changed since version 5.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectDataV5 {
+    pub billable: Vec<BillableEntry>,
+    pub projects: HashMap<String, ProjectV6>,
+    pub selected_project: Option<String>,
+    pub invoice_numbers: InvoiceSequence,
+    pub invoices: Vec<InvoiceV5>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VersionedProjectDataV5 {
+    pub version: u32,
+    pub data: ProjectDataV5
+}
+
+/// Invoice of schema versions 6 and 7, before LexOffice submissions.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InvoiceV7 {
+    pub number: String,
+    pub cancels: Option<String>,
+    pub project_id: String,
+    pub customer: Vec<String>,
+    pub date: String,
+    pub service_period: Option<ServicePeriod>,
+    pub line_items: Vec<LineItem>,
+    pub net: i64,
+    pub tax_rate: f32,
+    pub tax: i64,
+    pub gross: i64,
+    pub status: InvoiceStatus,
+    pub lex_office_id: Option<String>,
+    pub entries: Vec<u64>
+}
+
+/// Project data of schema version 6, before line grouping.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectDataV6 {
+    pub billable: Vec<BillableEntry>,
+    pub projects: HashMap<String, ProjectV6>,
+    pub selected_project: Option<String>,
+    pub invoice_numbers: InvoiceSequence,
+    pub invoices: Vec<InvoiceV7>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VersionedProjectDataV6 {
+    pub version: u32,
+    pub data: ProjectDataV6
+}
+
+/// Project data of schema version 7, before LexOffice submissions. `Project` has not changed since version 7.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectDataV7 {
+    pub billable: Vec<BillableEntry>,
+    pub projects: HashMap<String, Project>,
+    pub selected_project: Option<String>,
+    pub invoice_numbers: InvoiceSequence,
+    pub invoices: Vec<InvoiceV7>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VersionedProjectDataV7 {
+    pub version: u32,
+    pub data: ProjectDataV7
+}
+
+/// Invoice of schema versions 8 and 9, before LexOffice numbers.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InvoiceV9 {
+    pub number: String,
+    pub cancels: Option<String>,
+    pub project_id: String,
+    pub customer: Vec<String>,
+    pub date: String,
+    pub service_period: Option<ServicePeriod>,
+    pub line_items: Vec<LineItem>,
+    pub net: i64,
+    pub tax_rate: f32,
+    pub tax: i64,
+    pub gross: i64,
+    pub status: InvoiceStatus,
+    pub lex_office_id: Option<String>,
+    pub submission: Option<Submission>,
+    pub entries: Vec<u64>
+}
+
+/// Project data of schema versions 8 and 9, before LexOffice numbers.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectDataV9 {
+    pub billable: Vec<BillableEntry>,
+    pub projects: HashMap<String, Project>,
+    pub selected_project: Option<String>,
+    pub invoice_numbers: InvoiceSequence,
+    pub last_entry_id: u64,
+    pub invoices: Vec<InvoiceV9>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VersionedProjectDataV9 {
+    pub version: u32,
+    pub data: ProjectDataV9
+}
@@ -1,6 +1,116 @@
 use::serde::{Serialize, Deserialize};
 
+use crate::constants::{DEFAULT_BACKUP_COUNT, DEFAULT_INVOICE_NUMBER_PATTERN, DEFAULT_TAX_RATE};
+
+pub mod audit;
+pub mod document;
+pub mod integrity;
+pub mod invoice;
+pub mod legacy;
+pub mod project_data;
+pub mod suggestions;
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+pub enum StorageBackend {
+    #[default]
+    #[serde(rename = "rustbreak")]
+    Rustbreak,
+    #[serde(rename = "sqlite")]
+    Sqlite
+}
+
+/// File format of the rustbreak database.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+pub enum Encoding {
+    #[default]
+    #[serde(rename = "yaml")]
+    Yaml,
+    #[serde(rename = "bin")]
+    Bin,
+    #[serde(rename = "ron")]
+    Ron
+}
+
+/// Our own details, printed as the sender of invoices.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub struct Sender {
+    pub name: String,
+    #[serde(default)]
+    pub address: Vec<String>,
+    /// VAT id or tax number
+    pub tax_id: Option<String>
+}
+
+/// Account invoices are paid to.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub struct BankDetails {
+    pub account_holder: String,
+    pub iban: String,
+    pub bic: Option<String>,
+    pub bank_name: Option<String>
+}
+
+/// How hours generates invoice numbers, see `InvoiceNumberService` for the placeholders of `pattern`.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub struct InvoiceNumbering {
+    #[serde(default = "default_invoice_number_pattern")]
+    pub pattern: String,
+    /// Start the sequence over at 1 every year
+    #[serde(default)]
+    pub yearly_reset: bool
+}
+
+impl Default for InvoiceNumbering {
+    fn default() -> InvoiceNumbering {
+        InvoiceNumbering {
+            pattern: default_invoice_number_pattern(),
+            yearly_reset: false
+        }
+    }
+}
+
+fn default_invoice_number_pattern() -> String {
+    DEFAULT_INVOICE_NUMBER_PATTERN.to_string()
+}
+
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
 pub struct Config {
-    pub lex_office_api_key: Option<String>
-}
\ No newline at end of file
+    pub lex_office_api_key: Option<String>,
+    #[serde(default)]
+    pub storage: StorageBackend,
+    #[serde(default)]
+    pub encoding: Encoding,
+    /// Number of database backups to keep, 0 disables backups
+    #[serde(default = "default_backup_count")]
+    pub backup_count: usize,
+    pub sender: Option<Sender>,
+    pub bank: Option<BankDetails>,
+    /// VAT rate in percent added to invoices
+    #[serde(default = "default_tax_rate")]
+    pub tax_rate: f32,
+    #[serde(default)]
+    pub invoice_numbers: InvoiceNumbering
+}
+
+fn default_backup_count() -> usize {
+    DEFAULT_BACKUP_COUNT
+}
+
+fn default_tax_rate() -> f32 {
+    DEFAULT_TAX_RATE
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            lex_office_api_key: None,
+            storage: StorageBackend::default(),
+            encoding: Encoding::default(),
+            backup_count: DEFAULT_BACKUP_COUNT,
+            sender: None,
+            bank: None,
+            tax_rate: DEFAULT_TAX_RATE,
+            invoice_numbers: InvoiceNumbering::default()
+        }
+    }
+}
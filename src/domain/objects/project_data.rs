@@ -0,0 +1,154 @@
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Serialize, Deserialize};
+
+use crate::domain::objects::invoice::Invoice;
+
+pub type Money = u16;
+pub type Quantity = f32;
+
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum BillableUnit {
+    #[serde(rename = "day")]
+    Day,
+    #[serde(rename = "hour")]
+    Hour
+}
+
+/// How the entries of an invoice become its line items.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum LineGrouping {
+    /// One line per task
+    #[serde(rename = "task")]
+    Task,
+    #[serde(rename = "day")]
+    Day,
+    /// One line per ISO week
+    #[serde(rename = "week")]
+    Week,
+    /// One line per billable entry
+    #[serde(rename = "entry")]
+    Entry,
+    /// A single line with the total quantity
+    #[serde(rename = "single")]
+    Single
+}
+
+impl Default for LineGrouping {
+    fn default() -> Self {
+        return LineGrouping::Task
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BillableEntry {
+    /// Unique among all entries, invoices refer to entries by it. 0 in audit events logged before entries had ids.
+    #[serde(default)]
+    pub id: u64,
+    pub project_id: String,
+    pub task: String,
+    pub quantity: Quantity,
+    pub date: String
+}
+
+impl BillableEntry {
+    /// Whether both entries record the same work, regardless of their ids.
+    pub fn same_work(&self, other: &BillableEntry) -> bool {
+        return self.project_id == other.project_id && self.task == other.task && self.quantity == other.quantity && self.date == other.date;
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Billable {
+    pub id: u64,
+    pub project_id: String,
+    pub task: String,
+    pub quantity: Quantity,
+    pub date: DateTime<Local>
+}
+
+/// Selects the entries to invoice, unset bounds and no tasks select everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BillingFilter {
+    /// First day, inclusive
+    pub from: Option<NaiveDate>,
+    /// Last day, inclusive
+    pub until: Option<NaiveDate>,
+    pub tasks: Vec<String>
+}
+
+impl BillingFilter {
+    pub fn matches(&self, billable: &Billable) -> bool {
+        let day = billable.date.date().naive_local();
+
+        return self.from.is_none_or(|from| day >= from)
+            && self.until.is_none_or(|until| day <= until)
+            && (self.tasks.is_empty() || self.tasks.contains(&billable.task));
+    }
+}
+
+/// Projects are stored under a stable id, `name` is only displayed and can be changed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Project {
+    pub name: String,
+    pub unit_price: Money,
+    pub unit: BillableUnit,
+    pub tasks: HashSet<String>,
+    /// Additional slugs the project can be referred to by
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Postal address of the customer, one line per entry
+    #[serde(default)]
+    pub address: Vec<String>,
+    /// How entries become line items on invoices of the project
+    #[serde(default)]
+    pub grouping: LineGrouping
+}
+
+/// Invoice numbers handed out so far. Numbers must never be reused, so this only grows.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct InvoiceSequence {
+    /// Sequence number of the last number hours generated
+    pub last: u32,
+    /// Year the last number was generated in
+    pub year: Option<i32>,
+    /// Every invoice number in use, also those assigned by LexOffice
+    pub used: Vec<String>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ProjectData {
+    pub billable: Vec<BillableEntry>,
+    pub projects: HashMap<String, Project>,
+    #[serde(default)]
+    pub selected_project: Option<String>,
+    #[serde(default)]
+    pub invoice_numbers: InvoiceSequence,
+    /// Highest entry id given out so far, ids of deleted entries are not given out again
+    #[serde(default)]
+    pub last_entry_id: u64,
+    /// Issued invoices, oldest first
+    #[serde(default)]
+    pub invoices: Vec<Invoice>
+}
+
+impl ProjectData {
+    /// Id for a new entry, higher than every id given out before.
+    pub fn next_entry_id(&self) -> u64 {
+        return self.billable.iter().map(|e| e.id).max().unwrap_or(0).max(self.last_entry_id) + 1;
+    }
+
+    /// Adds `entry` and keeps its id from being given out again.
+    pub fn push_entry(&mut self, entry: BillableEntry) {
+        self.last_entry_id = self.last_entry_id.max(entry.id);
+        self.billable.push(entry);
+    }
+}
+
+/// On-disk form of `ProjectData`, `version` is the schema version the data was written in.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VersionedProjectData {
+    pub version: u32,
+    pub data: ProjectData
+}
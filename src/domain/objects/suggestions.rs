@@ -0,0 +1,27 @@
+use std::fmt;
+
+/// Known values similar to an unknown input, displayed as the end of an error message, e.g. ", did you mean foo or bar?".
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Suggestions(pub Vec<String>);
+
+impl fmt::Display for Suggestions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self.0.split_last() {
+            None => Ok(()),
+            Some((last, [])) => write!(f, ", did you mean {}?", last),
+            Some((last, rest)) => write!(f, ", did you mean {} or {}?", rest.join(", "), last)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Suggestions::default().to_string(), "");
+        assert_eq!(Suggestions(vec!["a".to_string()]).to_string(), ", did you mean a?");
+        assert_eq!(Suggestions(vec!["a".to_string(), "b".to_string(), "c".to_string()]).to_string(), ", did you mean a, b or c?");
+    }
+}
@@ -4,30 +4,113 @@ mod constants;
 mod builders;
 mod domain;
 
-use clap::{Arg, App};
-use crate::command_handlers::for_command;
+use std::process;
+use clap::{Arg, App, AppSettings, ArgSettings};
+use snafu::ResultExt;
+use crate::command_handlers::{for_command, worked_on_command, invoice_command, invoices_command, db_command, history_command, undo_command, sync_command, project_command, completions_command};
+use crate::command_handlers::invoice_command::InvoiceOptions;
+use crate::builders::config_builder::ConfigBuilder;
+use crate::builders::store_builder::StoreBuilder;
+use crate::constants::{CONFIG_FILE_PATH, EXIT_USAGE};
+use crate::domain::errors::app::{AppError, Config, Storage, Usage};
+use crate::domain::objects;
+use crate::services::file_database_service::ProjectDataService;
 
 const FOR_COMMAND: &str = "for";
 const WORKED_ON_COMMAND: &str = "worked-on";
 const INVOICE_COMMAND: &str = "invoice";
+const INVOICES_COMMAND: &str = "invoices";
+const PROJECT_COMMAND: &str = "project";
+const LIST_COMMAND: &str = "list";
+const ADD_COMMAND: &str = "add";
+const RENAME_COMMAND: &str = "rename";
+const ALIAS_COMMAND: &str = "alias";
+const HISTORY_COMMAND: &str = "history";
+const UNDO_COMMAND: &str = "undo";
+const SYNC_COMMAND: &str = "sync";
+const COMPLETIONS_COMMAND: &str = "completions";
+const COMPLETE_COMMAND: &str = "__complete";
+const DB_COMMAND: &str = "db";
+const MIGRATE_COMMAND: &str = "migrate";
+const CONVERT_COMMAND: &str = "convert";
+const BACKUP_COMMAND: &str = "backup";
+const RESTORE_COMMAND: &str = "restore";
+const CHECK_COMMAND: &str = "check";
+const ADDRESS_COMMAND: &str = "address";
+const SHOW_COMMAND: &str = "show";
+const CANCEL_COMMAND: &str = "cancel";
+const MARK_PAID_COMMAND: &str = "mark-paid";
+const GROUPING_COMMAND: &str = "grouping";
 
 const PROJECT_NAME_ARG: &str = "project_name";
+const RATE_ARG: &str = "rate";
+const UNIT_ARG: &str = "unit";
+const NEW_NAME_ARG: &str = "new_name";
+const ALIAS_ARG: &str = "alias";
+const ADDRESS_ARG: &str = "address";
+const GROUPING_ARG: &str = "grouping";
+const NEW_TASK_ARG: &str = "new";
 const TASK_ARG: &str = "task";
 const TIME_ARG: &str = "time";
 const DATE_ARG: &str = "date";
+const FORCE_ARG: &str = "force";
+const NUMBER_ARG: &str = "number";
+const PDF_ARG: &str = "pdf";
+const TEMPLATE_ARG: &str = "template";
+const OUT_ARG: &str = "out";
+const PERIOD_ARG: &str = "period";
+const FROM_ARG: &str = "from";
+const UNTIL_ARG: &str = "until";
+const PICK_ARG: &str = "pick";
+const DRY_RUN_ARG: &str = "dry-run";
+const LEX_OFFICE_ARG: &str = "lex-office";
+const TO_ARG: &str = "to";
+const LIST_ARG: &str = "list";
+const SNAPSHOT_ARG: &str = "snapshot";
+const LIMIT_ARG: &str = "limit";
+const REPLAY_ARG: &str = "replay";
+const CHECK_ARG: &str = "check";
+const FIX_ARG: &str = "fix";
+const SHELL_ARG: &str = "shell";
+const WORDS_ARG: &str = "words";
 
 fn main() {
-    let matches = App::new("Hours: Freelance billing app for Lex Office")
+    let matches = match app().try_get_matches() {
+        Ok(matches) => matches,
+        // Help and version are printed to stdout as asked for
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(EXIT_USAGE)
+        }
+    };
+
+    let result = run(matches);
+
+    match result {
+        Ok(output) => println!("{}", output),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(e.exit_code())
+        }
+    }
+}
+
+/// The command line interface, also walked by `__complete`.
+fn app() -> App<'static> {
+    return App::new("Hours: Freelance billing app for Lex Office")
         .version("0.1.0")
         .author("Pyry Kovanen")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
         .subcommands(vec![
             App::new(FOR_COMMAND)
                 .about("selects project in hours")
-                .arg(Arg::with_name(PROJECT_NAME_ARG)
-                    .takes_value(true)
-                    .about("Name of the project")
-                    .required(true)
-                ),
+                .args(vec![
+                    Arg::with_name(PROJECT_NAME_ARG)
+                        .takes_value(true)
+                        .about("Name of the project")
+                        .required(true)
+                ]),
             App::new(WORKED_ON_COMMAND)
                 .about("saves worked hours for current day, or given day if date argument is given")
                 .args(vec![
@@ -39,60 +122,425 @@ fn main() {
                         .about("Time spent on task")
                         .takes_value(true)
                         .required(true),
-                    Arg::new(DATE_ARG)
+                    Arg::with_name(NEW_TASK_ARG)
+                        .long(NEW_TASK_ARG)
+                        .about("Creates the task as typed, even when it looks like a typo of an existing task"),
+                    Arg::with_name(DATE_ARG)
                         .short('d')
-                        .about("Specify date instead of the default current date")
-                        .required(false)
+                        .long(DATE_ARG)
+                        .about("Specify date instead of the default current date, e.g. 2020-10-11, 11.10.2020, yesterday, mon or -2d")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .required(false),
+                    Arg::with_name(FORCE_ARG)
+                        .long(FORCE_ARG)
+                        .about("Accepts a date in the future")
                 ]),
             App::new(INVOICE_COMMAND)
                 .about("Creates invoice for customer, takes in all the hours that have not been billed yet until current date (inclusive)")
+                .setting(AppSettings::SubcommandsNegateReqs)
+                .subcommand(App::new(CANCEL_COMMAND)
+                    .about("Cancels an invoice with a cancellation invoice, its entries can be billed again. \
+                        Invoices created in LexOffice get a credit note there")
+                    .args(vec![
+                        Arg::with_name(NUMBER_ARG)
+                            .takes_value(true)
+                            .about("Number of the invoice, or the number LexOffice gave it")
+                            .required(true),
+                        Arg::with_name(PDF_ARG)
+                            .long(PDF_ARG)
+                            .takes_value(true)
+                            .value_name("file")
+                            .about("Renders the cancellation as PDF to the given file, only for invoices not in LexOffice")
+                    ])
+                )
                 .args(vec![
                     Arg::with_name(PROJECT_NAME_ARG)
                         .takes_value(true)
                         .about("Name of the project")
-                        .required(true)
+                        .required(true),
+                    Arg::with_name(PDF_ARG)
+                        .long(PDF_ARG)
+                        .takes_value(true)
+                        .value_name("file")
+                        .about("Renders the invoice as PDF to the given file, without LexOffice"),
+                    Arg::with_name(TEMPLATE_ARG)
+                        .long(TEMPLATE_ARG)
+                        .takes_value(true)
+                        .value_name("name")
+                        .requires(OUT_ARG)
+                        .about("Renders the invoice with a template from ~/.hou-rs/templates or a bundled one \
+                            (invoice or timesheet as html, md or typ), the extension of --out picks the format when the name has none"),
+                    Arg::with_name(OUT_ARG)
+                        .long(OUT_ARG)
+                        .takes_value(true)
+                        .value_name("file")
+                        .requires(TEMPLATE_ARG)
+                        .about("File the rendered template is written to"),
+                    Arg::with_name(PERIOD_ARG)
+                        .long(PERIOD_ARG)
+                        .takes_value(true)
+                        .value_name("start..end")
+                        .allow_hyphen_values(true)
+                        .about("Service period stated on the invoice, e.g. 2020-10-01..2020-10-31 or a single day. \
+                            Defaults to the days of the earliest and the latest billed entry"),
+                    Arg::with_name(FROM_ARG)
+                        .long(FROM_ARG)
+                        .takes_value(true)
+                        .value_name("date")
+                        .allow_hyphen_values(true)
+                        .about("Bills unbilled entries from this day on instead of all of them, e.g. 2020-09-01"),
+                    Arg::with_name(UNTIL_ARG)
+                        .long(UNTIL_ARG)
+                        .takes_value(true)
+                        .value_name("date")
+                        .allow_hyphen_values(true)
+                        .about("Bills unbilled entries up to this day instead of until today, e.g. 2020-09-30"),
+                    Arg::with_name(TASK_ARG)
+                        .long(TASK_ARG)
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .number_of_values(1)
+                        .about("Bills only entries of this task, can be given more than once"),
+                    Arg::with_name(PICK_ARG)
+                        .long(PICK_ARG)
+                        .about("Lists the entries to bill and asks which ones to leave for a later invoice"),
+                    Arg::with_name(DRY_RUN_ARG)
+                        .long(DRY_RUN_ARG)
+                        .conflicts_with_all(&[PDF_ARG, TEMPLATE_ARG])
+                        .about("Shows the line items, totals and the request that would be sent to LexOffice, \
+                            without saving or sending anything"),
+                    Arg::with_name(LEX_OFFICE_ARG)
+                        .long(LEX_OFFICE_ARG)
+                        .conflicts_with_all(&[DRY_RUN_ARG, PDF_ARG, TEMPLATE_ARG])
+                        .about("Creates the invoice in LexOffice, which numbers and renders it. \
+                            It is queued for `hours sync` when LexOffice is not available")
+                ]),
+            App::new(INVOICES_COMMAND)
+                .about("Shows the issued invoices and records their payment")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommands(vec![
+                    App::new(LIST_COMMAND)
+                        .about("Lists all invoices with their date, project, status and total"),
+                    App::new(SHOW_COMMAND)
+                        .about("Shows an invoice with its line items, totals and billed entries")
+                        .arg(Arg::with_name(NUMBER_ARG)
+                            .takes_value(true)
+                            .about("Number of the invoice, or the number LexOffice gave it")
+                            .required(true)
+                        ),
+                    App::new(MARK_PAID_COMMAND)
+                        .about("Marks an invoice as paid by the customer")
+                        .arg(Arg::with_name(NUMBER_ARG)
+                            .takes_value(true)
+                            .about("Number of the invoice, or the number LexOffice gave it")
+                            .required(true)
+                        )
+                ]),
+            App::new(PROJECT_COMMAND)
+                .about("Manages projects. Projects keep the id they were created with, names and aliases only help to find them")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommands(vec![
+                    App::new(LIST_COMMAND)
+                        .about("Lists all projects with their ids and aliases"),
+                    App::new(ADD_COMMAND)
+                        .about("Creates a project")
+                        .args(vec![
+                            Arg::with_name(PROJECT_NAME_ARG)
+                                .takes_value(true)
+                                .about("Name of the project")
+                                .required(true),
+                            Arg::with_name(RATE_ARG)
+                                .short('r')
+                                .long(RATE_ARG)
+                                .takes_value(true)
+                                .about("Unit price")
+                                .required(true),
+                            Arg::with_name(UNIT_ARG)
+                                .short('u')
+                                .long(UNIT_ARG)
+                                .takes_value(true)
+                                .possible_values(&["hour", "day"])
+                                .about("Billable unit, defaults to hour")
+                                .required(false)
+                        ]),
+                    App::new(RENAME_COMMAND)
+                        .about("Changes the name of a project, its entries stay with it")
+                        .args(vec![
+                            Arg::with_name(PROJECT_NAME_ARG)
+                                .takes_value(true)
+                                .about("Name, id or alias of the project")
+                                .required(true),
+                            Arg::with_name(NEW_NAME_ARG)
+                                .takes_value(true)
+                                .about("New name of the project")
+                                .required(true)
+                        ]),
+                    App::new(ALIAS_COMMAND)
+                        .about("Adds a short name the project can be referred to by")
+                        .args(vec![
+                            Arg::with_name(PROJECT_NAME_ARG)
+                                .takes_value(true)
+                                .about("Name, id or alias of the project")
+                                .required(true),
+                            Arg::with_name(ALIAS_ARG)
+                                .takes_value(true)
+                                .about("The alias to add")
+                                .required(true)
+                        ]),
+                    App::new(ADDRESS_COMMAND)
+                        .about("Sets the customer address printed on invoices of the project")
+                        .args(vec![
+                            Arg::with_name(PROJECT_NAME_ARG)
+                                .takes_value(true)
+                                .about("Name, id or alias of the project")
+                                .required(true),
+                            Arg::with_name(ADDRESS_ARG)
+                                .takes_value(true)
+                                .multiple(true)
+                                .about("Address lines, e.g. \"Acme GmbH\" \"Hauptstraße 1\" \"10115 Berlin\"")
+                                .required(true)
+                        ]),
+                    App::new(GROUPING_COMMAND)
+                        .about("Sets how entries become line items on invoices of the project: one line per task (the default), \
+                            per day, per week, per entry, or a single line with the total")
+                        .args(vec![
+                            Arg::with_name(PROJECT_NAME_ARG)
+                                .takes_value(true)
+                                .about("Name, id or alias of the project")
+                                .required(true),
+                            Arg::with_name(GROUPING_ARG)
+                                .takes_value(true)
+                                .possible_values(&["task", "day", "week", "entry", "single"])
+                                .required(true)
+                        ])
+                ]),
+            App::new(HISTORY_COMMAND)
+                .about("Shows the latest changes to the project data from the audit log")
+                .args(vec![
+                    Arg::with_name(LIMIT_ARG)
+                        .short('n')
+                        .long(LIMIT_ARG)
+                        .takes_value(true)
+                        .about("Number of changes to show, defaults to 20"),
+                    Arg::with_name(REPLAY_ARG)
+                        .long(REPLAY_ARG)
+                        .about("Rebuilds the database by replaying the whole audit log onto empty data")
+                ]),
+            App::new(UNDO_COMMAND)
+                .about("Reverts the latest change to the project data that was not undone yet")
+                .arg(Arg::with_name(LIST_ARG)
+                    .long(LIST_ARG)
+                    .about("Lists the changes that can be undone, latest first")
+                ),
+            App::new(SYNC_COMMAND)
+                .about("Creates the invoices queued for LexOffice, checking first whether earlier attempts reached it"),
+            App::new(COMPLETIONS_COMMAND)
+                .about("Prints the shell completion script, e.g. `hours completions bash > /etc/bash_completion.d/hours`")
+                .arg(Arg::with_name(SHELL_ARG)
+                    .takes_value(true)
+                    .possible_values(&["bash", "zsh", "fish"])
+                    .about("Shell to complete in")
+                    .required(true)
+                ),
+            App::new(COMPLETE_COMMAND)
+                .setting(AppSettings::Hidden)
+                .about("Prints completions for the given command line, used by the completion scripts")
+                .arg(Arg::with_name(WORDS_ARG)
+                    .takes_value(true)
+                    .multiple(true)
+                    .setting(ArgSettings::AllowEmptyValues)
+                ),
+            App::new(DB_COMMAND)
+                .about("Maintenance of the project database")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommands(vec![
+                    App::new(MIGRATE_COMMAND)
+                        .about("Copies the rustbreak database into another storage backend")
+                        .args(vec![
+                            Arg::with_name(TO_ARG)
+                                .long(TO_ARG)
+                                .takes_value(true)
+                                .possible_values(&["sqlite"])
+                                .about("Storage backend to migrate to")
+                                .required_unless(CHECK_ARG),
+                            Arg::with_name(CHECK_ARG)
+                                .long(CHECK_ARG)
+                                .conflicts_with(TO_ARG)
+                                .about("Shows the schema migrations the database needs without applying them")
+                        ]),
+                    App::new(CONVERT_COMMAND)
+                        .about("Rewrites the rustbreak database in another encoding")
+                        .arg(Arg::with_name(TO_ARG)
+                            .long(TO_ARG)
+                            .takes_value(true)
+                            .possible_values(&["yaml", "bin", "ron"])
+                            .about("Encoding to convert to")
+                            .required(true)
+                        ),
+                    App::new(BACKUP_COMMAND)
+                        .about("Backs up the project database")
+                        .arg(Arg::with_name(LIST_ARG)
+                            .long(LIST_ARG)
+                            .about("Lists the existing backups instead")
+                        ),
+                    App::new(CHECK_COMMAND)
+                        .about("Checks the project data for inconsistencies like entries of unknown projects")
+                        .arg(Arg::with_name(FIX_ARG)
+                            .long(FIX_ARG)
+                            .about("Repairs the problems that can be fixed safely and asks before removing duplicate entries")
+                        ),
+                    App::new(RESTORE_COMMAND)
+                        .about("Replaces the project database with a backup")
+                        .args(vec![
+                            Arg::with_name(SNAPSHOT_ARG)
+                                .takes_value(true)
+                                .about("Name of the backup, or a path to it")
+                                .required(true),
+                            Arg::with_name(FORCE_ARG)
+                                .long(FORCE_ARG)
+                                .about("Restores a backup even when it holds no projects or entries")
+                        ])
                 ])
-        ])
-        .get_matches();
+        ]);
+}
+
+fn run(matches: clap::ArgMatches) -> Result<String, AppError> {
+    let config = ConfigBuilder::new()
+        .using_toml()
+        .from(CONFIG_FILE_PATH.to_string())
+        .build()
+        .context(Config)?;
 
-    let result: String = match matches.subcommand() {
+    if let (COMPLETIONS_COMMAND, Some(completions)) = matches.subcommand() {
+        return completions_command::handle_script(completions.value_of(SHELL_ARG))
+    }
+
+    if let (COMPLETE_COMMAND, Some(complete)) = matches.subcommand() {
+        let words: Vec<&str> = complete.values_of(WORDS_ARG).map(|w| w.collect()).unwrap_or_default();
+        return completions_command::handle_complete(&app(), &words, |arg| complete_value(&config, arg))
+    }
+
+    if let (HISTORY_COMMAND, Some(history)) = matches.subcommand() {
+        return history_command::handle(&config, history.value_of(LIMIT_ARG), history.is_present(REPLAY_ARG))
+    }
+
+    if let (DB_COMMAND, Some(db)) = matches.subcommand() {
+        return match db.subcommand() {
+            (MIGRATE_COMMAND, Some(migrate)) =>
+                db_command::handle_migrate(&config, migrate.value_of(TO_ARG), migrate.is_present(CHECK_ARG)),
+            (CONVERT_COMMAND, Some(convert)) =>
+                db_command::handle_convert(&config, convert.value_of(TO_ARG)),
+            (BACKUP_COMMAND, Some(backup)) =>
+                db_command::handle_backup(&config, backup.is_present(LIST_ARG)),
+            (CHECK_COMMAND, Some(check)) =>
+                db_command::handle_check(&config, check.is_present(FIX_ARG)),
+            (RESTORE_COMMAND, Some(restore)) =>
+                db_command::handle_restore(&config, restore.value_of(SNAPSHOT_ARG), restore.is_present(FORCE_ARG)),
+            _ =>
+                handle_unknown()
+        }
+    }
+
+    let service = project_data_service(&config)?;
+
+    return match matches.subcommand() {
         (FOR_COMMAND, Some(register_project)) =>
             for_command::handle(
+                &service,
                 register_project.value_of(PROJECT_NAME_ARG)
             ),
         (WORKED_ON_COMMAND, Some(worked)) =>
-            handle_worked_on(
+            worked_on_command::handle(
+                &service,
                 worked.value_of(TASK_ARG),
-                worked.value_of(TIME_ARG)
+                worked.value_of(TIME_ARG),
+                worked.value_of(DATE_ARG),
+                worked.is_present(NEW_TASK_ARG),
+                worked.is_present(FORCE_ARG)
             ),
-        (INVOICE_COMMAND, Some(worked)) =>
-            handle_invoice(
-                worked.value_of(PROJECT_NAME_ARG)
+        (INVOICE_COMMAND, Some(invoice)) if invoice.subcommand_name() == Some(CANCEL_COMMAND) => {
+            let cancel = invoice.subcommand_matches(CANCEL_COMMAND).expect("cancel was given");
+            invoice_command::handle_cancel(&config, &service, cancel.value_of(NUMBER_ARG), cancel.value_of(PDF_ARG))
+        },
+        (INVOICE_COMMAND, Some(invoice)) =>
+            invoice_command::handle(
+                &config,
+                &service,
+                invoice.value_of(PROJECT_NAME_ARG),
+                &InvoiceOptions {
+                    pdf: invoice.value_of(PDF_ARG),
+                    template: invoice.value_of(TEMPLATE_ARG).zip(invoice.value_of(OUT_ARG)),
+                    period: invoice.value_of(PERIOD_ARG),
+                    from: invoice.value_of(FROM_ARG),
+                    until: invoice.value_of(UNTIL_ARG),
+                    tasks: invoice.values_of(TASK_ARG).map(|t| t.collect()).unwrap_or_default(),
+                    pick: invoice.is_present(PICK_ARG),
+                    dry_run: invoice.is_present(DRY_RUN_ARG),
+                    lex_office: invoice.is_present(LEX_OFFICE_ARG)
+                }
             ),
+        (INVOICES_COMMAND, Some(invoices)) => match invoices.subcommand() {
+            (LIST_COMMAND, Some(_)) =>
+                invoices_command::handle_list(&service),
+            (SHOW_COMMAND, Some(show)) =>
+                invoices_command::handle_show(&service, show.value_of(NUMBER_ARG)),
+            (MARK_PAID_COMMAND, Some(mark_paid)) =>
+                invoices_command::handle_mark_paid(&service, mark_paid.value_of(NUMBER_ARG)),
+            _ =>
+                handle_unknown()
+        },
+        (PROJECT_COMMAND, Some(project)) => match project.subcommand() {
+            (LIST_COMMAND, Some(_)) =>
+                project_command::handle_list(&service),
+            (ADD_COMMAND, Some(add)) =>
+                project_command::handle_add(&service, add.value_of(PROJECT_NAME_ARG), add.value_of(RATE_ARG), add.value_of(UNIT_ARG)),
+            (RENAME_COMMAND, Some(rename)) =>
+                project_command::handle_rename(&service, rename.value_of(PROJECT_NAME_ARG), rename.value_of(NEW_NAME_ARG)),
+            (ALIAS_COMMAND, Some(alias)) =>
+                project_command::handle_alias(&service, alias.value_of(PROJECT_NAME_ARG), alias.value_of(ALIAS_ARG)),
+            (ADDRESS_COMMAND, Some(address)) =>
+                project_command::handle_address(&service, address.value_of(PROJECT_NAME_ARG), address.values_of(ADDRESS_ARG).map(|v| v.collect())),
+            (GROUPING_COMMAND, Some(grouping)) =>
+                project_command::handle_grouping(&service, grouping.value_of(PROJECT_NAME_ARG), grouping.value_of(GROUPING_ARG)),
+            _ =>
+                handle_unknown()
+        },
+        (UNDO_COMMAND, Some(undo)) =>
+            undo_command::handle(&service, undo.is_present(LIST_ARG)),
+        (SYNC_COMMAND, Some(_)) =>
+            sync_command::handle(&config, &service),
         _ =>
             handle_unknown()
     };
-
-
-    println!("{}", result)
 }
 
-fn handle_worked_on(task: Option<&str>, time: Option<&str>) -> String {
-    if let (Some(tsk), Some(tme)) = (task, time) {
-        return format!("Marked {} to {}", tme, tsk)
+/// Live values of positional arguments for `__complete`.
+fn complete_value(config: &objects::Config, arg: &str) -> Result<Vec<String>, AppError> {
+    return match arg {
+        PROJECT_NAME_ARG => completions_command::project_names(&read_only_service(config)?),
+        TASK_ARG => completions_command::task_names(&read_only_service(config)?),
+        NUMBER_ARG => completions_command::invoice_numbers(&read_only_service(config)?),
+        _ => Ok(vec![])
     }
-
-    return String::new()
 }
 
-fn handle_invoice(project_name: Option<&str>) -> String {
-    if let Some(name) = project_name {
-        return format!("{}", name)
-    }
+fn project_data_service(config: &objects::Config) -> Result<ProjectDataService, AppError> {
+    return StoreBuilder::new()
+        .using(config.storage.clone())
+        .encoded(config.encoding.clone())
+        .build_service(config.backup_count)
+        .context(Storage);
+}
 
-    return String::new()
+/// Service for completions, which run on every TAB press and must not wait for the lock or migrate the database.
+fn read_only_service(config: &objects::Config) -> Result<ProjectDataService, AppError> {
+    return StoreBuilder::new()
+        .using(config.storage.clone())
+        .build_read_only()
+        .context(Storage);
 }
 
-fn handle_unknown() -> String {
-    return String::new()
+fn handle_unknown() -> Result<String, AppError> {
+    return Usage { message: "Unknown command, see `hours --help`" }.fail()
 }
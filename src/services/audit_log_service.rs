@@ -0,0 +1,299 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::Path;
+use chrono::Local;
+use serde_json::Value;
+use snafu::{OptionExt, ResultExt};
+
+use crate::constants::USER_ENV_KEY;
+use crate::domain::errors::project_data::{ProjectDataError, AuditLogEntry, CreateDataDirectory, LegacyAuditEvent, NoSnapshot, ReadDataFile, ReplayEvent, SerializeAuditEvent, WriteDataFile};
+use crate::domain::objects::audit::{AuditEvent, Operation};
+use crate::domain::objects::invoice::Invoice;
+use crate::domain::objects::project_data::{BillableEntry, ProjectData};
+
+
+/// Append-only JSON lines log of all changes made to the project data.
+pub struct AuditLogService {
+    path: String
+}
+
+impl AuditLogService {
+    pub fn new(path: String) -> AuditLogService {
+        AuditLogService {
+            path
+        }
+    }
+
+    pub fn append(&self, events: &[AuditEvent]) -> Result<(), ProjectDataError> {
+        if events.is_empty() {
+            return Ok(())
+        }
+
+        if let Some(parent) = Path::new(&self.path).parent() {
+            fs::create_dir_all(parent).context(CreateDataDirectory { path: parent.to_string_lossy() })?;
+        }
+
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(&serde_json::to_string(event).context(SerializeAuditEvent)?);
+            lines.push('\n');
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context(WriteDataFile { path: &self.path })?;
+        file.write_all(lines.as_bytes()).context(WriteDataFile { path: &self.path })?;
+
+        return file.sync_all().context(WriteDataFile { path: &self.path });
+    }
+
+    /// Whether nothing was logged yet.
+    pub fn is_empty(&self) -> Result<bool, ProjectDataError> {
+        return match fs::metadata(&self.path) {
+            Ok(metadata) => Ok(metadata.len() == 0),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(true),
+            Err(e) => Err(e).context(ReadDataFile { path: &self.path })
+        }
+    }
+
+    /// Event holding a full copy of `data`. Replays start from the latest one.
+    pub fn snapshot_event(data: &ProjectData) -> Result<AuditEvent, ProjectDataError> {
+        return Ok(AuditEvent {
+            timestamp: Local::now().to_string(),
+            user: env::var(USER_ENV_KEY).ok(),
+            operation: Operation::Restore,
+            target: String::new(),
+            before: None,
+            after: Some(serde_json::to_value(data).context(SerializeAuditEvent)?),
+            batch: None,
+            undoes: None
+        })
+    }
+
+    /// All logged events, oldest first.
+    pub fn read(&self) -> Result<Vec<AuditEvent>, ProjectDataError> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e).context(ReadDataFile { path: &self.path })
+        };
+
+        let mut events = vec![];
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.context(ReadDataFile { path: &self.path })?;
+
+            if line.trim().is_empty() {
+                continue
+            }
+            events.push(serde_json::from_str(&line).context(AuditLogEntry { line: index + 1 })?);
+        }
+
+        return Ok(events);
+    }
+
+    /// Rebuilds project data from the latest snapshot in `events` and the events after it.
+    /// Logs without a snapshot and events that refer to entries from before entries had ids are refused.
+    pub fn replay(events: &[AuditEvent]) -> Result<ProjectData, ProjectDataError> {
+        let start = events.iter()
+            .rposition(|e| e.operation == Operation::Restore && !matches!(e.after, None | Some(Value::Null)))
+            .context(NoSnapshot)?;
+        let mut data = ProjectData::default();
+
+        for event in &events[start..] {
+            if event.refers_to_legacy_entry() {
+                return LegacyAuditEvent { timestamp: &event.timestamp }.fail()
+            }
+            AuditLogService::apply(&mut data, event).context(ReplayEvent { timestamp: &event.timestamp })?;
+        }
+
+        return Ok(data);
+    }
+
+    /// Logged operations that can still be undone, latest first, with the events of each in logged order.
+    /// Operations already undone are skipped, the search stops at the first operation that blocks undo.
+    pub fn undoable(events: &[AuditEvent]) -> Vec<Vec<AuditEvent>> {
+        let undone: HashSet<&String> = events.iter().filter_map(|e| e.undoes.as_ref()).collect();
+
+        let mut batches: Vec<Vec<AuditEvent>> = vec![];
+        for event in events {
+            match batches.last_mut() {
+                Some(batch) if batch[0].batch_id() == event.batch_id() => batch.push(event.clone()),
+                _ => batches.push(vec![event.clone()])
+            }
+        }
+
+        let mut undoable = vec![];
+        for batch in batches.into_iter().rev() {
+            if batch.iter().any(|e| e.operation.blocks_undo()) {
+                break
+            }
+            if batch.iter().any(|e| e.undoes.is_some()) || undone.contains(batch[0].batch_id()) {
+                continue
+            }
+            undoable.push(batch);
+        }
+
+        return undoable;
+    }
+
+    /// Changes `data` from the event's `before` to its `after` state.
+    pub fn apply(data: &mut ProjectData, event: &AuditEvent) -> Result<(), serde_json::Error> {
+        match (&event.operation, &event.after) {
+            (Operation::AddProject, None) | (Operation::UpdateProject, None) | (Operation::AddTask, None) => {
+                data.projects.remove(&event.target);
+            },
+            (Operation::AddProject, Some(after)) | (Operation::UpdateProject, Some(after)) | (Operation::AddTask, Some(after)) => {
+                data.projects.insert(event.target.to_string(), serde_json::from_value(after.clone())?);
+            },
+            (Operation::AddBillableEntry, None) | (Operation::RemoveBillableEntry, None) => {
+                let before: BillableEntry = serde_json::from_value(event.before.clone().unwrap_or(Value::Null))?;
+                // Entries logged before entries had ids are found by their content
+                if let Some(index) = data.billable.iter().rposition(|e| e == &before || (before.id == 0 && e.same_work(&before))) {
+                    data.billable.remove(index);
+                }
+            },
+            (Operation::AddBillableEntry, Some(after)) | (Operation::RemoveBillableEntry, Some(after)) => {
+                data.push_entry(serde_json::from_value(after.clone())?);
+            },
+            (Operation::SelectProject, after) => {
+                data.selected_project = serde_json::from_value(after.clone().unwrap_or(Value::Null))?;
+            },
+            (Operation::IssueInvoiceNumber, after) => {
+                data.invoice_numbers = serde_json::from_value(after.clone().unwrap_or(Value::Null))?;
+            },
+            (Operation::AddInvoice, None) | (Operation::UpdateInvoice, None) => {
+                data.invoices.retain(|i| i.number != event.target);
+            },
+            (Operation::AddInvoice, Some(after)) | (Operation::UpdateInvoice, Some(after)) => {
+                let invoice: Invoice = serde_json::from_value(after.clone())?;
+                match data.invoices.iter_mut().find(|i| i.number == invoice.number) {
+                    Some(existing) => *existing = invoice,
+                    None => data.invoices.push(invoice)
+                }
+            },
+            (Operation::Restore, after) => {
+                *data = serde_json::from_value(after.clone().unwrap_or(Value::Null))?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+    use crate::domain::objects::project_data::{BillableUnit, LineGrouping, Project};
+
+    fn temp_path(name: &str) -> String {
+        let path = env::temp_dir().join(format!("hours-audit-{}-{}.jsonl", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+
+        return path.to_string_lossy().to_string();
+    }
+
+    fn event(operation: Operation, target: &str, after: serde_json::Value) -> AuditEvent {
+        return AuditEvent {
+            timestamp: "2020-10-11 22:09:24.269707 +02:00".to_string(),
+            user: None,
+            operation,
+            target: target.to_string(),
+            before: None,
+            after: Some(after),
+            batch: None,
+            undoes: None
+        }
+    }
+
+    fn mock_events() -> Vec<AuditEvent> {
+        let project = Project { name: "Foo".to_string(), unit_price: 80, unit: BillableUnit::Hour, tasks: HashSet::new(), aliases: vec![], address: vec![], grouping: LineGrouping::Task };
+        let entry = BillableEntry {
+            id: 1,
+            project_id: "foo".to_string(),
+            task: "development".to_string(),
+            quantity: 8.0,
+            date: "2020-10-11 22:09:24.269707 +02:00".to_string()
+        };
+
+        return vec![
+            event(Operation::AddProject, "foo", serde_json::to_value(&project).unwrap()),
+            event(Operation::AddBillableEntry, "foo", serde_json::to_value(&entry).unwrap()),
+            event(Operation::SelectProject, "foo", serde_json::to_value("foo").unwrap())
+        ]
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let path = temp_path("append");
+        let service = AuditLogService::new(path.clone());
+
+        service.append(&mock_events()[..1]).unwrap();
+        service.append(&mock_events()[1..]).unwrap();
+
+        assert_eq!(service.read().unwrap(), mock_events());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_read_missing_log() {
+        let service = AuditLogService::new(temp_path("missing"));
+
+        assert_eq!(service.read().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut events = vec![AuditLogService::snapshot_event(&ProjectData::default()).unwrap()];
+        events.extend(mock_events());
+
+        let data = AuditLogService::replay(&events).unwrap();
+
+        assert_eq!(data.projects.get("foo").map(|p| p.unit_price), Some(80));
+        assert_eq!(data.billable.len(), 1);
+        assert_eq!(data.selected_project, Some("foo".to_string()));
+    }
+
+    #[test]
+    fn test_replay_starts_from_latest_snapshot() {
+        let snapshot = ProjectData { selected_project: Some("bar".to_string()), ..ProjectData::default() };
+        let mut events = mock_events();
+        events.push(AuditLogService::snapshot_event(&snapshot).unwrap());
+
+        assert_eq!(AuditLogService::replay(&events).unwrap(), snapshot);
+        assert_eq!(AuditLogService::replay(&mock_events()).is_err(), true);
+    }
+
+    #[test]
+    fn test_replay_refuses_entries_without_id() {
+        let mut legacy = mock_events()[1].clone();
+        legacy.after.as_mut().unwrap().as_object_mut().unwrap().remove("id");
+        let events = vec![AuditLogService::snapshot_event(&ProjectData::default()).unwrap(), mock_events()[0].clone(), legacy];
+
+        assert_eq!(AuditLogService::replay(&events).is_err(), true);
+    }
+
+    #[test]
+    fn test_undoable_skips_undone_and_stops_at_restore() {
+        let batch = |mut event: AuditEvent, batch: &str| { event.batch = Some(batch.to_string()); event };
+        let mut undo = batch(event(Operation::AddProject, "bar", serde_json::Value::Null), "4");
+        undo.undoes = Some("3".to_string());
+        let events = vec![
+            batch(event(Operation::AddProject, "foo", serde_json::Value::Null), "1"),
+            batch(event(Operation::Restore, "", serde_json::Value::Null), "2"),
+            batch(event(Operation::AddProject, "baz", serde_json::Value::Null), "2b"),
+            batch(event(Operation::SelectProject, "baz", serde_json::Value::Null), "2b"),
+            batch(event(Operation::AddProject, "bar", serde_json::Value::Null), "3"),
+            undo
+        ];
+
+        let undoable = AuditLogService::undoable(&events);
+
+        assert_eq!(undoable.len(), 1);
+        assert_eq!(undoable[0].iter().map(|e| e.target.as_str()).collect::<Vec<&str>>(), vec!["baz", "baz"]);
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::Local;
+use std::fs;
+use std::path::Path;
+use snafu::ResultExt;
+
+use crate::domain::errors::project_data::{ProjectDataError, CreateDataDirectory, UnknownBackup, WriteDataFile};
+use crate::services::project_store::ProjectStore;
+
+
+/// Keeps up to `keep` timestamped copies of the database in `directory`.
+pub struct BackupService {
+    directory: String,
+    prefix: String,
+    keep: usize
+}
+
+impl BackupService {
+    /// `prefix` names the backups, usually the file name of the database.
+    pub fn new(directory: String, prefix: String, keep: usize) -> BackupService {
+        BackupService {
+            directory,
+            prefix,
+            keep
+        }
+    }
+
+    /// Backs up the last saved state of `store` and removes the oldest backups over the limit.
+    /// Returns the path of the new backup, or `None` when backups are disabled or there was nothing to back up.
+    pub fn backup(&self, store: &dyn ProjectStore) -> Result<Option<String>, ProjectDataError> {
+        if self.keep == 0 {
+            return Ok(None)
+        }
+
+        fs::create_dir_all(&self.directory).context(CreateDataDirectory { path: &self.directory })?;
+
+        let name = format!("{}-{}", self.prefix, Local::now().format("%Y%m%d-%H%M%S%.3f"));
+        let path = self.path_of(&name);
+        store.backup(&path)?;
+
+        self.prune()?;
+
+        if Path::new(&path).exists() {
+            return Ok(Some(path))
+        }
+
+        return Ok(None)
+    }
+
+    /// Names of the existing backups, oldest first.
+    pub fn list(&self) -> Result<Vec<String>, ProjectDataError> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(_) => return Ok(vec![])
+        };
+        let prefix = format!("{}-", self.prefix);
+
+        let mut names: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with(&prefix))
+            .collect();
+        names.sort();
+
+        return Ok(names);
+    }
+
+    /// Path of a backup given either its name or a path to it.
+    pub fn resolve(&self, snapshot: &String) -> Result<String, ProjectDataError> {
+        if self.list()?.contains(snapshot) {
+            return Ok(self.path_of(snapshot))
+        }
+
+        if Path::new(snapshot).is_file() {
+            return Ok(snapshot.to_string())
+        }
+
+        return UnknownBackup { snapshot }.fail()
+    }
+
+    fn prune(&self) -> Result<(), ProjectDataError> {
+        let names = self.list()?;
+        let excess = names.len().saturating_sub(self.keep);
+
+        for name in names.iter().take(excess) {
+            let path = self.path_of(name);
+            fs::remove_file(&path).context(WriteDataFile { path })?;
+        }
+
+        return Ok(());
+    }
+
+    fn path_of(&self, name: &String) -> String {
+        return Path::new(&self.directory).join(name).to_string_lossy().to_string();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+    use crate::domain::objects::project_data::ProjectData;
+    use crate::services::rustbreak_store;
+    use crate::domain::objects::Encoding;
+
+    fn temp_dir(name: &str) -> String {
+        let path = env::temp_dir().join(format!("hours-backup-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+
+        return path.to_string_lossy().to_string();
+    }
+
+    #[test]
+    fn test_backups_are_rotated() {
+        let directory = temp_dir("rotated");
+        let database = format!("{}/projects", directory);
+        let store = rustbreak_store::open(database, Encoding::Yaml).unwrap();
+        store.replace(ProjectData::default()).unwrap();
+        store.save().unwrap();
+
+        let service = BackupService::new(format!("{}/backups", directory), "projects".to_string(), 2);
+        for _ in 0..3 {
+            service.backup(&*store).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        assert_eq!(service.list().unwrap().len(), 2);
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_backups_disabled() {
+        let directory = temp_dir("disabled");
+        let store = rustbreak_store::open(format!("{}/projects", directory), Encoding::Yaml).unwrap();
+
+        let service = BackupService::new(format!("{}/backups", directory), "projects".to_string(), 0);
+
+        assert_eq!(service.backup(&*store).unwrap(), None);
+        assert_eq!(service.list().unwrap().len(), 0);
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_resolve_unknown_backup() {
+        let directory = temp_dir("unknown");
+        let service = BackupService::new(directory.clone(), "projects".to_string(), 2);
+
+        assert_eq!(service.resolve(&"projects-19700101-000000.000".to_string()).is_err(), true);
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
@@ -1,5 +1,8 @@
+use std::io::ErrorKind;
+
 use crate::services::toml_service::{TomlFileService, TomlFileServiceTrait};
 use crate::domain::objects::Config;
+use crate::domain::errors::toml_file::FileError;
 use crate::services::file_path_service::FilePathService;
 
 
@@ -17,24 +20,21 @@ impl ConfigFileService {
     }
 
     fn get_empty_config(&self) -> Config {
-        return Config {
-            lex_office_api_key: None
-        }
+        return Config::default()
     }
 
-    pub fn read_config(self) -> Config {
-        let default = self.get_empty_config();
-        let get_config= move || {
-            let path = FilePathService::absolute_path(&self.config_path)?;
-            return self.toml_service.read_from_file::<Config>(&path);
-        };
+    /// Reads the config file. A missing file falls back to the default config,
+    /// any other failure (unreadable file, invalid TOML) is returned as an error.
+    pub fn read_config(self) -> Result<Config, FileError> {
+        let path = FilePathService::absolute_path(&self.config_path)?;
 
-        return match get_config() {
-            Ok(config) => config,
-            Err(e) => {
-                println!("No config found, moving forward with default config: {}", e);
-                return default
-            }
+        return match self.toml_service.read_from_file::<Config>(&path) {
+            Ok(config) => Ok(config),
+            Err(FileError::ReadFile { ref source, .. }) if source.kind() == ErrorKind::NotFound => {
+                eprintln!("No config found at {}, moving forward with default config", path);
+                Ok(self.get_empty_config())
+            },
+            Err(e) => Err(e)
         }
     }
 }
@@ -46,25 +46,43 @@ mod test {
 
     #[test]
     fn read_config_actual() {
-        let expected = Config{lex_office_api_key: Some("apikey".to_string())};
+        let expected = Config{lex_office_api_key: Some("apikey".to_string()), ..Config::default()};
         let config_file_service = ConfigFileService::new(
             "test_helpers/mock_config.toml".to_string(),
             TomlFileService::new(FileService::new())
         );
 
-        let res = config_file_service.read_config();
+        let res = config_file_service.read_config().unwrap();
         assert_eq!(res, expected)
     }
 
     #[test]
     fn read_config_default() {
-        let expected = Config{lex_office_api_key: None};
+        let expected = Config::default();
         let config_file_service = ConfigFileService::new(
             "/mock_config.toml".to_string(),
             TomlFileService::new(FileService::new())
         );
 
-        let res = config_file_service.read_config();
+        let res = config_file_service.read_config().unwrap();
         assert_eq!(res, expected)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn read_config_invalid() {
+        let config_file_service = ConfigFileService::new(
+            "test_helpers/invalid_config.toml".to_string(),
+            TomlFileService::new(FileService::new())
+        );
+
+        let res = config_file_service.read_config();
+
+        match res {
+            Err(FileError::DeserializeToml { line, column, .. }) => {
+                assert_eq!(line, Some(1));
+                assert_eq!(column, Some(22));
+            },
+            _ => panic!("Expected a deserialization error, got {:?}", res)
+        }
+    }
+}
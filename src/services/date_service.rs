@@ -0,0 +1,117 @@
+use std::convert::TryFrom;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+const WEEKDAYS: [(Weekday, &str, &str); 7] = [
+    (Weekday::Mon, "mon", "monday"),
+    (Weekday::Tue, "tue", "tuesday"),
+    (Weekday::Wed, "wed", "wednesday"),
+    (Weekday::Thu, "thu", "thursday"),
+    (Weekday::Fri, "fri", "friday"),
+    (Weekday::Sat, "sat", "saturday"),
+    (Weekday::Sun, "sun", "sunday")
+];
+
+pub const ACCEPTED_FORMATS: &str = "2020-10-11, 11.10.2020, today, yesterday, a weekday like mon, or an offset like -2d or -1w";
+
+pub struct DateService;
+
+impl DateService {
+    /// Parses a date as typed on the command line, relative dates are counted from `today`.
+    /// Weekdays mean the most recent one, today included.
+    pub fn parse(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+        let input = input.trim().to_lowercase();
+
+        match input.as_str() {
+            "today" => return Some(today),
+            "yesterday" => return Some(today - Duration::days(1)),
+            _ => {}
+        }
+
+        if let Some((weekday, _, _)) = WEEKDAYS.iter().find(|(_, short, long)| input == *short || input == *long) {
+            let days_back = (7 + today.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+            return Some(today - Duration::days(days_back as i64))
+        }
+
+        if let Some(offset) = input.strip_prefix('-') {
+            let (amount, unit) = offset.split_at(offset.len().saturating_sub(1));
+            let amount = amount.parse::<i64>().ok()?;
+            let days = match unit {
+                "d" => amount,
+                "w" => amount.checked_mul(7)?,
+                _ => return None
+            };
+            // Within i32 the duration can not overflow, dates out of range are `None`
+            return today.checked_sub_signed(Duration::days(i32::try_from(days).ok()? as i64))
+        }
+
+        return NaiveDate::parse_from_str(&input, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(&input, "%d.%m.%Y"))
+            .ok();
+    }
+
+    /// Parses a range of days like `2020-10-01..2020-10-31`, a single date is a range of one day.
+    /// `None` when a side is no date or the range ends before it starts.
+    pub fn parse_range(input: &str, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+        let (start, end) = match input.split_once("..") {
+            Some((start, end)) => (DateService::parse(start, today)?, DateService::parse(end, today)?),
+            None => {
+                let day = DateService::parse(input, today)?;
+                (day, day)
+            }
+        };
+
+        if end < start {
+            return None
+        }
+
+        return Some((start, end));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A Wednesday
+    fn today() -> NaiveDate {
+        return NaiveDate::from_ymd(2020, 10, 14);
+    }
+
+    #[test]
+    fn test_parse_absolute_dates() {
+        assert_eq!(DateService::parse("2020-10-11", today()), Some(NaiveDate::from_ymd(2020, 10, 11)));
+        assert_eq!(DateService::parse("11.10.2020", today()), Some(NaiveDate::from_ymd(2020, 10, 11)));
+        assert_eq!(DateService::parse("1.2.2020", today()), Some(NaiveDate::from_ymd(2020, 2, 1)));
+        assert_eq!(DateService::parse("31.02.2020", today()), None);
+    }
+
+    #[test]
+    fn test_parse_relative_dates() {
+        assert_eq!(DateService::parse("today", today()), Some(today()));
+        assert_eq!(DateService::parse("Yesterday", today()), Some(NaiveDate::from_ymd(2020, 10, 13)));
+        assert_eq!(DateService::parse("-2d", today()), Some(NaiveDate::from_ymd(2020, 10, 12)));
+        assert_eq!(DateService::parse("-1w", today()), Some(NaiveDate::from_ymd(2020, 10, 7)));
+        assert_eq!(DateService::parse("-d", today()), None);
+        assert_eq!(DateService::parse("-99999999999d", today()), None);
+        assert_eq!(DateService::parse("-9223372036854775807w", today()), None);
+        assert_eq!(DateService::parse("-2000000000d", today()), None);
+        assert_eq!(DateService::parse_range("-99999999999d..today", today()), None);
+    }
+
+    #[test]
+    fn test_parse_weekdays() {
+        assert_eq!(DateService::parse("mon", today()), Some(NaiveDate::from_ymd(2020, 10, 12)));
+        assert_eq!(DateService::parse("wednesday", today()), Some(today()));
+        assert_eq!(DateService::parse("thu", today()), Some(NaiveDate::from_ymd(2020, 10, 8)));
+        assert_eq!(DateService::parse("someday", today()), None);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(DateService::parse_range("2020-10-01..31.10.2020", today()), Some((NaiveDate::from_ymd(2020, 10, 1), NaiveDate::from_ymd(2020, 10, 31))));
+        assert_eq!(DateService::parse_range("-1w..today", today()), Some((NaiveDate::from_ymd(2020, 10, 7), today())));
+        assert_eq!(DateService::parse_range("yesterday", today()), Some((NaiveDate::from_ymd(2020, 10, 13), NaiveDate::from_ymd(2020, 10, 13))));
+        assert_eq!(DateService::parse_range("2020-10-31..2020-10-01", today()), None);
+        assert_eq!(DateService::parse_range("2020-10-01..", today()), None);
+    }
+}
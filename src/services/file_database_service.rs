@@ -1,99 +1,383 @@
-use rustbreak::{FileDatabase, RustbreakError};
-use rustbreak::deser::{Yaml};
-use std::collections::{HashMap, HashSet};
-use chrono::{DateTime, Local, Datelike};
-use serde::{Serialize, Deserialize};
-
-type DB = FileDatabase<ProjectData, Yaml>;
-type Money = u16;
-type Quantity = f32;
-
-
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-enum BillableUnit {
-    #[serde(rename = "day")]
-    Day,
-    #[serde(rename = "hour")]
-    Hour
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-struct BillableEntry {
-    project_id: String,
-    task: String,
-    quantity: Quantity,
-    date: String
-}
-
-#[derive(Debug, PartialEq)]
-struct Billable {
-    project_id: String,
-    task: String,
-    quantity: Quantity,
-    date: DateTime<Local>
-}
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::env;
+use chrono::{DateTime, Local, Datelike, NaiveDate};
+use serde::Serialize;
+use snafu::{OptionExt, ResultExt};
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-struct Project {
-    name: String,
-    unit_price: Money,
-    unit: BillableUnit,
-    tasks: HashSet<String>
-}
+use crate::constants::{LEX_OFFICE_REFERENCE_PREFIX, USER_ENV_KEY};
+use crate::domain::errors::project_data::{ProjectDataError, AliasTaken, AmbiguousProject, AmbiguousTask, InvoiceNumberUsed, NotCancellable, NotPayable, NotQueued, ProjectExists, ReplayEvent, SerializeAuditEvent, UnknownInvoice, UnknownProject, UnknownTask};
+use crate::domain::objects::audit::{AuditEvent, Operation};
+use crate::domain::objects::InvoiceNumbering;
+use crate::domain::objects::integrity::Problem;
+use crate::domain::objects::invoice::{Invoice, InvoiceStatus, Submission};
+use crate::domain::objects::project_data::{Billable, BillableEntry, BillingFilter, BillableUnit, InvoiceSequence, LineGrouping, Money, Project, ProjectData, Quantity};
+use crate::domain::objects::suggestions::Suggestions;
+use crate::services::audit_log_service::AuditLogService;
+use crate::services::backup_service::BackupService;
+use crate::services::invoice_number_service::InvoiceNumberService;
+use crate::services::invoice_service::InvoiceService;
+use crate::services::match_service::{Match, MatchService};
+use crate::services::project_store::ProjectStore;
+use crate::services::slug_service::SlugService;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct ProjectData {
-    billable: Vec<BillableEntry>,
-    projects: HashMap<String, Project>
-}
 
-struct ProjectDataService {
-    db: DB
+pub struct ProjectDataService {
+    store: Box<dyn ProjectStore>,
+    backups: Option<BackupService>,
+    audit_log: Option<AuditLogService>,
+    /// Changes made since the last save, written to the audit log when they are saved
+    pending_events: RefCell<Vec<AuditEvent>>,
+    /// Snapshot of the data as loaded, logged before the first change when the audit log is new
+    genesis: RefCell<Option<AuditEvent>>,
+    /// Batch of the latest save, continued by `write_progress_to_file`
+    batch: RefCell<Option<String>>
 }
 
 impl ProjectDataService {
-    pub fn new(path: String) -> ProjectDataService {
-        let db: DB = FileDatabase::create_at_path(path, ProjectData {
-            billable: vec![],
-            projects: HashMap::new(),
-        }).unwrap();
-        let _ = db.load();
+    pub fn new(store: Box<dyn ProjectStore>) -> ProjectDataService {
+        return ProjectDataService {
+            store,
+            backups: None,
+            audit_log: None,
+            pending_events: RefCell::new(vec![]),
+            genesis: RefCell::new(None),
+            batch: RefCell::new(None)
+        }
+    }
 
+    /// Backs up the previously saved data before every save.
+    pub fn with_backups(self, backups: BackupService) -> ProjectDataService {
         return ProjectDataService {
-            db
+            backups: Some(backups),
+            ..self
         }
     }
 
-    pub fn add_project(&self, project_name: &String, unit_price: Money, unit: BillableUnit) {
-        let project_id = self.get_project_id(&project_name);
+    /// Appends every saved change to the audit log. A new log starts with a snapshot of the data, so that it can be replayed.
+    pub fn with_audit_log(self, audit_log: AuditLogService) -> Result<ProjectDataService, ProjectDataError> {
+        let genesis = match audit_log.is_empty()? {
+            true => Some(AuditLogService::snapshot_event(&self.store.snapshot()?)?),
+            false => None
+        };
+
+        return Ok(ProjectDataService {
+            audit_log: Some(audit_log),
+            genesis: RefCell::new(genesis),
+            ..self
+        })
+    }
+
+    /// Creates a project under the slug of its name, e.g. "Café GmbH" is stored as "cafe-gmbh".
+    pub fn add_project(&self, project_name: &String, unit_price: Money, unit: BillableUnit) -> Result<(), ProjectDataError> {
+        if self.find_project_by_name(project_name)?.is_some() {
+            return ProjectExists { project: project_name }.fail()
+        }
+
+        // The slug may still be the id of a project that was renamed since
+        let projects = self.store.get_projects()?;
+        let project_id = SlugService::unique(&SlugService::slugify(project_name), |id| projects.contains_key(id));
         let project = Project{
             unit_price,
             unit,
             name: project_name.to_string(),
-            tasks: HashSet::new()
+            tasks: HashSet::new(),
+            aliases: vec![],
+            address: vec![],
+            grouping: LineGrouping::Task
         };
-        let _ = self.db.write(|db| {
-            db.projects.insert(project_id, project)
-        });
+
+        self.record(Operation::AddProject, &project_id, None::<Project>, Some(&project))?;
+        return self.store.insert_project(&project_id, project);
+    }
+
+    pub fn get_project(&self, project_name: &String) -> Result<Option<Project>, ProjectDataError> {
+        return match self.find_project_id(project_name)? {
+            Some(project_id) => self.store.get_project(&project_id),
+            None => Ok(None)
+        }
+    }
+
+    /// All projects by id, sorted by id.
+    pub fn get_projects(&self) -> Result<Vec<(String, Project)>, ProjectDataError> {
+        let mut projects: Vec<(String, Project)> = self.store.get_projects()?.into_iter().collect();
+        projects.sort_by(|a, b| a.0.cmp(&b.0));
+
+        return Ok(projects);
+    }
+
+    /// Changes the displayed name, the id and with it all entries of the project stay the same.
+    pub fn rename_project(&self, project_name: &String, new_name: &String) -> Result<(), ProjectDataError> {
+        let project_id = self.resolve_project_id(project_name)?;
+
+        match self.find_project_by_name(new_name)? {
+            Some(other) if other != project_id => return ProjectExists { project: new_name }.fail(),
+            _ => {}
+        }
+
+        return self.update_project(&project_id, |project| project.name = new_name.to_string());
+    }
+
+    /// Lets the project be referred to by the slug of `alias`.
+    pub fn add_alias(&self, project_name: &String, alias: &String) -> Result<(), ProjectDataError> {
+        let project_id = self.resolve_project_id(project_name)?;
+        let alias = SlugService::slugify(alias);
+
+        return match self.find_project_id(&alias)? {
+            Some(other) if other == project_id => Ok(()),
+            Some(other) => AliasTaken { alias, project: other }.fail(),
+            None => self.update_project(&project_id, |project| project.aliases.push(alias.to_string()))
+        }
+    }
+
+    /// Generates the next invoice number for an invoice issued on `date` and marks it as used.
+    /// Numbers already in use, e.g. from before the pattern was changed, are skipped.
+    pub fn issue_invoice_number(&self, numbering: &InvoiceNumbering, date: NaiveDate) -> Result<String, ProjectDataError> {
+        let before = self.store.get_invoice_sequence()?;
+        let (sequence, number) = InvoiceNumberService::next_unused(&before, numbering, date)?;
+
+        let mut after = before.clone();
+        after.last = sequence;
+        after.year = Some(date.year());
+        after.used.push(number.to_string());
+        self.set_invoice_sequence(&number, before, after)?;
+
+        return Ok(number);
+    }
+
+    /// The number `issue_invoice_number` would issue, without marking it as used.
+    pub fn peek_invoice_number(&self, numbering: &InvoiceNumbering, date: NaiveDate) -> Result<String, ProjectDataError> {
+        let sequence = self.store.get_invoice_sequence()?;
+
+        return Ok(InvoiceNumberService::next_unused(&sequence, numbering, date)?.1);
+    }
+
+    /// Reference for an invoice issued on `date` that LexOffice numbers, e.g. `LX-2020-10-31-1`.
+    /// It does not use up a number of the sequence, which would then never reach a customer and leave a gap.
+    pub fn lex_office_reference(&self, date: NaiveDate) -> Result<String, ProjectDataError> {
+        let numbers: HashSet<String> = self.store.get_invoices()?.into_iter().map(|i| i.number).collect();
+
+        return Ok((1..)
+            .map(|n| format!("{}-{}-{}", LEX_OFFICE_REFERENCE_PREFIX, date.format("%Y-%m-%d"), n))
+            .find(|reference| !numbers.contains(reference))
+            .unwrap());
+    }
+
+    /// Marks an invoice number assigned outside of hours, e.g. by LexOffice, as used.
+    pub fn record_invoice_number(&self, number: &String) -> Result<(), ProjectDataError> {
+        let before = self.store.get_invoice_sequence()?;
+        if before.used.contains(number) {
+            return InvoiceNumberUsed { number }.fail()
+        }
+
+        let mut after = before.clone();
+        after.used.push(number.to_string());
+
+        return self.set_invoice_sequence(number, before, after);
+    }
+
+    /// Records an issued invoice, its entries count as billed from now on.
+    pub fn add_invoice(&self, invoice: Invoice) -> Result<(), ProjectDataError> {
+        self.record(Operation::AddInvoice, &invoice.number, None::<Invoice>, Some(&invoice))?;
+
+        return self.store.insert_invoice(invoice);
     }
 
-    pub fn add_task(&self, project_name: &String, task_name: &String) {
-        let project_id = self.get_project_id(&project_name);
-        let _ = self.db.write(|db| {
-            if let Some(p) = db.projects.get_mut(&project_id) {
-                return p.tasks.insert(task_name.to_string())
+    /// All invoices, oldest first.
+    pub fn get_invoices(&self) -> Result<Vec<Invoice>, ProjectDataError> {
+        return self.store.get_invoices();
+    }
+
+    /// Issues a cancellation of the invoice `number` dated `date` and marks the invoice as cancelled,
+    /// its entries can be billed again. Returns the cancellation. Cancellations of invoices in LexOffice are numbered
+    /// by LexOffice, they get a reference instead of a number of the sequence.
+    pub fn cancel_invoice(&self, number: &String, numbering: &InvoiceNumbering, date: NaiveDate) -> Result<Invoice, ProjectDataError> {
+        let invoice = self.get_invoice(number)?;
+        if let Some(cancelled) = &invoice.cancels {
+            return NotCancellable { number, reason: format!("it is the cancellation of {}", cancelled) }.fail()
+        }
+        if invoice.status == InvoiceStatus::Cancelled {
+            return NotCancellable { number, reason: "it is already cancelled" }.fail()
+        }
+        // Attempted submissions may have reached LexOffice, unless LexOffice rejected them
+        if invoice.submission.as_ref().is_some_and(|s| s.attempts > 0 && !s.rejected) {
+            return NotCancellable { number, reason: "it may already exist in LexOffice, run `hours sync` first" }.fail()
+        }
+
+        let cancellation_number = match invoice.lex_office_id {
+            Some(_) => self.lex_office_reference(date)?,
+            None => self.issue_invoice_number(numbering, date)?
+        };
+        let cancellation = InvoiceService::cancellation(&invoice, &cancellation_number, date);
+        self.add_invoice(cancellation.clone())?;
+        self.update_invoice(number, |invoice| {
+            invoice.status = InvoiceStatus::Cancelled;
+            invoice.submission = None;
+        })?;
+
+        return Ok(cancellation);
+    }
+
+    /// Marks the invoice `number` as paid by the customer.
+    pub fn mark_paid(&self, number: &String) -> Result<(), ProjectDataError> {
+        let invoice = self.get_invoice(number)?;
+        if let Some(cancelled) = &invoice.cancels {
+            return NotPayable { number, reason: format!("it is the cancellation of {}", cancelled) }.fail()
+        }
+        match invoice.status {
+            InvoiceStatus::Cancelled => return NotPayable { number, reason: "it is cancelled" }.fail(),
+            InvoiceStatus::Paid => return NotPayable { number, reason: "it is already paid" }.fail(),
+            _ => {}
+        }
+
+        return self.update_invoice(number, |invoice| invoice.status = InvoiceStatus::Paid);
+    }
+
+    /// Queues the invoice `number` to be created in LexOffice.
+    pub fn queue_submission(&self, number: &String, submission: Submission) -> Result<(), ProjectDataError> {
+        return self.update_invoice(number, |invoice| invoice.submission = Some(submission));
+    }
+
+    /// Invoices waiting to be created in LexOffice, queued first comes first. Invoices LexOffice rejected are not waiting.
+    pub fn get_queued_invoices(&self) -> Result<Vec<Invoice>, ProjectDataError> {
+        let mut queued: Vec<Invoice> = self.store.get_invoices()?.into_iter().filter(|i| i.submission.as_ref().is_some_and(|s| !s.rejected)).collect();
+        queued.sort_by(|a, b| a.submission.as_ref().map(|s| &s.queued).cmp(&b.submission.as_ref().map(|s| &s.queued)));
+
+        return Ok(queued);
+    }
+
+    /// Counts an attempt to create the queued invoice `number` in LexOffice.
+    /// Returns the submission as it was before, so earlier attempts that may have reached LexOffice can be checked.
+    pub fn record_submission_attempt(&self, number: &String) -> Result<Submission, ProjectDataError> {
+        let submission = self.get_invoice(number)?.submission.context(NotQueued { number })?;
+        self.update_invoice(number, |invoice| {
+            if let Some(submission) = invoice.submission.as_mut() {
+                submission.attempts += 1;
+            }
+        })?;
+
+        return Ok(submission);
+    }
+
+    /// Records why the latest submission of the invoice `number` failed. It stays queued unless LexOffice `rejected` it.
+    pub fn record_submission_failure(&self, number: &String, error: &String, rejected: bool) -> Result<(), ProjectDataError> {
+        return self.update_invoice(number, |invoice| {
+            if let Some(submission) = invoice.submission.as_mut() {
+                submission.last_error = Some(error.to_string());
+                submission.rejected = rejected;
             }
-            return false
         });
     }
 
+    /// Takes the invoice `number` off the queue and marks it as sent, it was created as the voucher `lex_office_id`
+    /// numbered `lex_office_number`.
+    pub fn complete_submission(&self, number: &String, lex_office_id: &String, lex_office_number: Option<&String>) -> Result<(), ProjectDataError> {
+        return self.update_invoice(number, |invoice| {
+            invoice.lex_office_id = Some(lex_office_id.to_string());
+            invoice.lex_office_number = lex_office_number.cloned();
+            invoice.submission = None;
+            invoice.status = InvoiceStatus::Sent;
+        });
+    }
 
-    pub fn add_billable_entry(&self, project_name: &String, task: &String, quantity: Quantity, date: Option<DateTime<Local>>) -> Result<(), RustbreakError> {
-        let project_id = self.get_project_id(&project_name);
-        let is_known_task = self.task_exists(&project_id, &task)?;
+    /// Invoice with the number or LexOffice number `number`.
+    pub fn get_invoice(&self, number: &String) -> Result<Invoice, ProjectDataError> {
+        return self.store.get_invoices()?
+            .into_iter()
+            .find(|i| &i.number == number || i.lex_office_number.as_ref() == Some(number))
+            .context(UnknownInvoice { number });
+    }
+
+    /// Replaces the customer address printed on the project's invoices.
+    pub fn set_address(&self, project_name: &String, address: Vec<String>) -> Result<(), ProjectDataError> {
+        let project_id = self.resolve_project_id(project_name)?;
+
+        return self.update_project(&project_id, |project| project.address = address);
+    }
+
+    pub fn set_grouping(&self, project_name: &String, grouping: LineGrouping) -> Result<(), ProjectDataError> {
+        let project_id = self.resolve_project_id(project_name)?;
+
+        return self.update_project(&project_id, |project| project.grouping = grouping);
+    }
+
+    /// Id of the project `project_name` refers to: exactly by id, name or alias, or as the only project starting with it.
+    pub fn resolve_project_id(&self, project_name: &String) -> Result<String, ProjectDataError> {
+        if let Some(project_id) = self.find_project_id(project_name)? {
+            return Ok(project_id)
+        }
+
+        let mut candidates = vec![];
+        for (project_id, project) in self.store.get_projects()? {
+            candidates.push((project_id.to_string(), project_id.to_string()));
+            candidates.push((SlugService::slugify(&project.name), project_id.to_string()));
+            candidates.extend(project.aliases.iter().map(|alias| (alias.to_string(), project_id.to_string())));
+        }
+
+        return match MatchService::find(&SlugService::slugify(project_name), &candidates) {
+            Match::Found(project_id) => Ok(project_id),
+            Match::Ambiguous(candidates) => AmbiguousProject { project: project_name, candidates }.fail(),
+            Match::NotFound { suggestions } => UnknownProject { project: project_name, suggestions }.fail()
+        }
+    }
+
+    /// Task of the project that `task_name` refers to: exactly, or as the only task starting with it.
+    /// Any other name is a new task, unless it looks like a typo of an existing task and `allow_new` is false.
+    pub fn resolve_task(&self, project_name: &String, task_name: &String, allow_new: bool) -> Result<String, ProjectDataError> {
+        let project_id = self.resolve_project_id(project_name)?;
+        let tasks = self.store.get_project(&project_id)?.map(|p| p.tasks).unwrap_or_default();
+
+        if allow_new || tasks.contains(task_name) {
+            return Ok(task_name.to_string())
+        }
+
+        let candidates: Vec<(String, String)> = tasks.iter()
+            .map(|task| (SlugService::slugify(task), task.to_string()))
+            .collect();
+
+        return match MatchService::find(&SlugService::slugify(task_name), &candidates) {
+            Match::Found(task) => Ok(task),
+            Match::Ambiguous(candidates) => AmbiguousTask { task: task_name, candidates }.fail(),
+            Match::NotFound { suggestions } => UnknownTask { project: project_name, task: task_name, suggestions }.fail()
+        }
+    }
+
+    pub fn add_task(&self, project_name: &String, task_name: &String) -> Result<(), ProjectDataError> {
+        let project_id = self.resolve_project_id(project_name)?;
+        let before = self.store.get_project(&project_id)?;
+        let is_known_project = self.store.insert_task(&project_id, task_name)?;
+
+        if !is_known_project {
+            return UnknownProject { project: project_name, suggestions: Suggestions::default() }.fail()
+        }
+
+        let after = self.store.get_project(&project_id)?;
+        if before != after {
+            self.record(Operation::AddTask, &project_id, before, after)?;
+        }
+
+        return Ok(());
+    }
+
+    pub fn select_project(&self, project_name: &String) -> Result<(), ProjectDataError> {
+        let project_id = self.resolve_project_id(project_name)?;
+
+        let before = self.store.get_selected_project()?;
+        self.record(Operation::SelectProject, &project_id, Some(before), Some(Some(&project_id)))?;
+        return self.store.set_selected_project(&project_id);
+    }
+
+    /// Id of the selected project, it can be used wherever a project name is expected.
+    pub fn get_selected_project_id(&self) -> Result<Option<String>, ProjectDataError> {
+        return self.store.get_selected_project();
+    }
+
+    pub fn add_billable_entry(&self, project_name: &String, task: &String, quantity: Quantity, date: Option<DateTime<Local>>) -> Result<(), ProjectDataError> {
+        let project_id = self.resolve_project_id(project_name)?;
+        let is_known_task = self.task_exists(&project_id, task)?;
 
         if !is_known_task {
-            return Err(RustbreakError::Poison)
+            return UnknownTask { project: project_name, task, suggestions: Suggestions::default() }.fail()
         }
 
         let date_str = match date {
@@ -102,52 +386,245 @@ impl ProjectDataService {
         };
 
         let billable = BillableEntry{
-            project_id,
+            id: self.store.next_entry_id()?,
+            project_id: project_id.to_string(),
             task: task.to_string(),
             quantity,
             date: date_str
         };
 
-        let _ = self.db.write(|db| {
-            db.billable.insert(db.billable.len(), billable)
-        });
+        self.record(Operation::AddBillableEntry, &project_id, None::<BillableEntry>, Some(&billable))?;
+        return self.store.insert_entry(billable);
+    }
 
-        return Ok(());
+    /// Entries not billed by an invoice yet that match `filter`, oldest first.
+    pub fn get_unbilled(&self, project_name: &String, filter: &BillingFilter) -> Result<Vec<Billable>, ProjectDataError> {
+        let project_id = self.resolve_project_id(project_name)?;
+
+        let mut billing: Vec<Billable> = self.unbilled(&project_id)?
+            .into_iter()
+            .filter(|e| filter.matches(e))
+            .collect();
+        billing.sort_by_key(|e| e.date);
+
+        return Ok(billing);
     }
 
-    pub fn get_monthly_billing(&self, project_name: &String, date: Option<DateTime<Local>>) -> Result<Vec<Billable>, RustbreakError> {
-        let project_id = self.get_project_id(&project_name);
-        let month = match date {
-            None => Local::now().month(),
-            Some(d) => d.month()
+    /// Whether an invoice that is not cancelled bills the entry `entry_id`.
+    pub fn is_billed(&self, entry_id: u64) -> Result<bool, ProjectDataError> {
+        return Ok(self.store.get_invoices()?.iter().any(|i| i.bills(entry_id)));
+    }
+
+    fn unbilled(&self, project_id: &String) -> Result<Vec<Billable>, ProjectDataError> {
+        let invoices = self.store.get_invoices()?;
+
+        return Ok(self.store.get_entries(project_id)?
+            .into_iter()
+            .filter(|e| !invoices.iter().any(|i| i.bills(e.id)))
+            .filter_map(|e| Some(Billable{ id: e.id, date: e.date.parse::<DateTime<Local>>().ok()?, project_id: e.project_id, quantity: e.quantity, task: e.task }))
+            .collect());
+    }
+
+    /// Returns a copy of all data.
+    pub fn snapshot(&self) -> Result<ProjectData, ProjectDataError> {
+        return self.store.snapshot();
+    }
+
+    /// Repairs a problem found by the integrity check, returns false when it can not be fixed or was already fixed.
+    pub fn repair(&self, problem: &Problem) -> Result<bool, ProjectDataError> {
+        match problem {
+            Problem::UnknownTask { project_id, task } => {
+                let before = self.store.get_project(project_id)?;
+                if !self.store.insert_task(project_id, task)? {
+                    return Ok(false)
+                }
+                let after = self.store.get_project(project_id)?;
+                if before == after {
+                    return Ok(false)
+                }
+                self.record(Operation::AddTask, project_id, before, after)?;
+            },
+            Problem::DuplicateEntry { entry } => {
+                // Entries on invoices are kept, identical work can be billed twice
+                if self.is_billed(entry.id)? || !self.store.remove_entry(entry)? {
+                    return Ok(false)
+                }
+                self.record(Operation::RemoveBillableEntry, &entry.project_id, Some(entry), None::<BillableEntry>)?;
+            },
+            Problem::UnknownSelectedProject { project_id } => {
+                self.store.clear_selected_project()?;
+                self.record(Operation::SelectProject, project_id, Some(Some(project_id)), Some(None::<String>))?;
+            },
+            Problem::UnknownProject { .. } | Problem::InvalidDate { .. } | Problem::NegativeQuantity { .. } => return Ok(false)
+        }
+
+        return Ok(true);
+    }
+
+    /// Replaces all data, for example with the contents of a backup.
+    pub fn restore(&self, data: ProjectData) -> Result<(), ProjectDataError> {
+        self.record(Operation::Restore, &String::new(), None::<ProjectData>, Some(&data))?;
+
+        return self.store.replace(data);
+    }
+
+    /// Logged operations that `undo` can revert, latest first.
+    pub fn undoable(&self) -> Result<Vec<Vec<AuditEvent>>, ProjectDataError> {
+        return match &self.audit_log {
+            Some(audit_log) => Ok(AuditLogService::undoable(&audit_log.read()?)),
+            None => Ok(vec![])
+        }
+    }
+
+    /// Reverts the latest operation that was not undone yet and returns its events, `None` when there is nothing to undo.
+    pub fn undo(&self) -> Result<Option<Vec<AuditEvent>>, ProjectDataError> {
+        let events = match self.undoable()?.into_iter().next() {
+            Some(events) => events,
+            None => return Ok(None)
         };
 
-        return self.db.read( |db| {
-           db.billable.clone()
-               .into_iter()
-               .filter_map(|e| Some(Billable{ date: e.date.parse::<DateTime<Local>>().ok()?, project_id: e.project_id, quantity: e.quantity, task: e.task }))
-               .filter(|e| e.project_id == project_id)
-               .filter(|e| e.date.month() == month)
-               .collect()
-        });
+        let mut data = self.store.snapshot()?;
+        for event in events.iter().rev() {
+            let inverse = AuditEvent {
+                timestamp: Local::now().to_string(),
+                user: env::var(USER_ENV_KEY).ok(),
+                before: event.after.clone(),
+                after: event.before.clone(),
+                batch: None,
+                undoes: Some(event.batch_id().to_string()),
+                ..event.clone()
+            };
+            AuditLogService::apply(&mut data, &inverse).context(ReplayEvent { timestamp: &event.timestamp })?;
+            self.pending_events.borrow_mut().push(inverse);
+        }
+        self.store.replace(data)?;
+
+        return Ok(Some(events));
+    }
+
+    pub fn write_to_file(&self) -> Result<(), ProjectDataError> {
+        let batch = Local::now().to_string();
+        self.batch.replace(Some(batch.clone()));
+        if let Some(backups) = &self.backups {
+            backups.backup(&*self.store)?;
+        }
+
+        return self.save(batch);
     }
 
-    pub fn write_to_file(&self) -> Result<(), RustbreakError> {
-        self.db.save()
+    /// Saves like `write_to_file`, but as part of the batch of the previous save and without another backup.
+    /// For bookkeeping while a command runs, e.g. submission attempts.
+    pub fn write_progress_to_file(&self) -> Result<(), ProjectDataError> {
+        let batch = self.batch.borrow().clone();
+        let batch = match batch {
+            Some(batch) => batch,
+            None => return self.write_to_file()
+        };
+
+        return self.save(batch);
     }
 
-    fn task_exists(&self, project_id: &String, task_name: &String) -> Result<bool, RustbreakError> {
-        return self.db.read(|db| {
-            if let Some(p) = db.projects.get(project_id) {
-                return p.tasks.contains(task_name);
+    fn save(&self, batch: String) -> Result<(), ProjectDataError> {
+        let mut events = self.pending_events.replace(vec![]);
+        for event in events.iter_mut() {
+            event.batch = Some(batch.clone());
+        }
+        // Logged ahead of the save, so that the log never misses a saved change
+        if let Some(audit_log) = &self.audit_log {
+            if !events.is_empty() {
+                if let Some(mut genesis) = self.genesis.borrow_mut().take() {
+                    genesis.batch = Some(format!("{} snapshot", batch));
+                    audit_log.append(&[genesis])?;
+                }
             }
+            audit_log.append(&events)?;
+        }
 
-            return false
-        });
+        return self.store.save();
     }
 
-    fn get_project_id(&self, project_name: &String) -> String {
-        return project_name.to_ascii_lowercase();
+    fn record<B: Serialize, A: Serialize>(&self, operation: Operation, target: &String, before: Option<B>, after: Option<A>) -> Result<(), ProjectDataError> {
+        let event = AuditEvent {
+            timestamp: Local::now().to_string(),
+            user: env::var(USER_ENV_KEY).ok(),
+            operation,
+            target: target.to_string(),
+            before: before.map(serde_json::to_value).transpose().context(SerializeAuditEvent)?,
+            after: after.map(serde_json::to_value).transpose().context(SerializeAuditEvent)?,
+            batch: None,
+            undoes: None
+        };
+
+        self.pending_events.borrow_mut().push(event);
+
+        return Ok(());
+    }
+
+    fn task_exists(&self, project_id: &String, task_name: &String) -> Result<bool, ProjectDataError> {
+        return match self.store.get_project(project_id)? {
+            Some(p) => Ok(p.tasks.contains(task_name)),
+            None => Ok(false)
+        }
+    }
+
+    fn set_invoice_sequence(&self, number: &String, before: InvoiceSequence, after: InvoiceSequence) -> Result<(), ProjectDataError> {
+        self.record(Operation::IssueInvoiceNumber, number, Some(&before), Some(&after))?;
+
+        return self.store.set_invoice_sequence(after);
+    }
+
+    fn update_invoice<F: FnOnce(&mut Invoice)>(&self, number: &String, change: F) -> Result<(), ProjectDataError> {
+        let before = self.get_invoice(number)?;
+        let mut after = before.clone();
+        change(&mut after);
+
+        if before != after {
+            self.record(Operation::UpdateInvoice, &before.number, Some(&before), Some(&after))?;
+            self.store.insert_invoice(after)?;
+        }
+
+        return Ok(());
+    }
+
+    fn update_project<F: FnOnce(&mut Project)>(&self, project_id: &String, change: F) -> Result<(), ProjectDataError> {
+        let before = self.store.get_project(project_id)?.context(UnknownProject { project: project_id, suggestions: Suggestions::default() })?;
+        let mut after = before.clone();
+        change(&mut after);
+
+        if before != after {
+            self.record(Operation::UpdateProject, project_id, Some(&before), Some(&after))?;
+            self.store.insert_project(project_id, after)?;
+        }
+
+        return Ok(());
+    }
+
+
+    /// Id of the project `project_name` refers to by name or alias, or else by id.
+    fn find_project_id(&self, project_name: &String) -> Result<Option<String>, ProjectDataError> {
+        if let Some(project_id) = self.find_project_by_name(project_name)? {
+            return Ok(Some(project_id))
+        }
+
+        let slug = SlugService::slugify(project_name);
+        if self.store.get_project(&slug)?.is_some() {
+            return Ok(Some(slug))
+        }
+
+        return Ok(None);
+    }
+
+    /// Id of the project whose name or one of whose aliases has the same slug as `project_name`.
+    fn find_project_by_name(&self, project_name: &String) -> Result<Option<String>, ProjectDataError> {
+        let slug = SlugService::slugify(project_name);
+        let mut matches: Vec<String> = self.store.get_projects()?
+            .into_iter()
+            .filter(|(_, p)| p.aliases.contains(&slug) || SlugService::slugify(&p.name) == slug)
+            .map(|(project_id, _)| project_id)
+            .collect();
+        matches.sort();
+
+        return Ok(matches.into_iter().next());
     }
 }
 
@@ -155,14 +632,23 @@ impl ProjectDataService {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::services::file_database_service::BillableUnit::Day;
-
-    const DB_FILE: &str = "test_helpers/db.yaml";
+    use crate::domain::objects::project_data::BillableUnit::Day;
+    use crate::domain::objects::project_data::ProjectData;
+    use crate::services::integrity_service::IntegrityService;
+    use crate::services::memory_store::MemoryStore;
     const MOCK_PROJECT_NAME: &str = "Foo";
     const MOCK_PROJECT_ID: &str = "foo";
     const EXPECTED_TASK_NAME: &str = "development";
     const UNEXPECTED_TASK_NAME: &str = "destruction";
 
+    /// Replays the unsaved changes of a service that started out with empty data.
+    fn replay_pending(service: &ProjectDataService) -> ProjectData {
+        let mut events = vec![AuditLogService::snapshot_event(&ProjectData::default()).unwrap()];
+        events.extend(service.pending_events.borrow().iter().cloned());
+
+        return AuditLogService::replay(&events).unwrap();
+    }
+
     #[test]
     fn test_add_project() {
         let project_name = &MOCK_PROJECT_NAME.to_string();
@@ -171,14 +657,16 @@ mod test {
             name: project_name.to_string(),
             unit_price: 80,
             unit: Day,
-            tasks: HashSet::new()
+            tasks: HashSet::new(),
+            aliases: vec![],
+            address: vec![],
+            grouping: LineGrouping::Task
         };
-        let service = ProjectDataService::new(DB_FILE.to_string());
-        service.add_project(project_name, 80, Day);
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
 
-        let _ = service.db.read(|db| {
-            assert_eq!(db.projects.get(MOCK_PROJECT_ID), Some(&expected))
-        });
+        let project = service.store.get_project(&MOCK_PROJECT_ID.to_string()).unwrap();
+        assert_eq!(project, Some(expected));
 
     }
 
@@ -194,15 +682,17 @@ mod test {
             name: project_name.to_string(),
             unit_price: 80,
             unit: Day,
-            tasks: set
+            tasks: set,
+            aliases: vec![],
+            address: vec![],
+            grouping: LineGrouping::Task
         };
-        let service = ProjectDataService::new(DB_FILE.to_string());
-        service.add_project(project_name, 80, Day);
-        service.add_task(project_name, task_name);
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, task_name).unwrap();
 
-        let _ = service.db.read(|db| {
-            assert_eq!(db.projects.get(MOCK_PROJECT_ID), Some(&expected))
-        });
+        let project = service.store.get_project(&MOCK_PROJECT_ID.to_string()).unwrap();
+        assert_eq!(project, Some(expected));
     }
 
     #[test]
@@ -213,22 +703,22 @@ mod test {
         let expected_task = &EXPECTED_TASK_NAME.to_string();
         let unexpected_task = &UNEXPECTED_TASK_NAME.to_string();
         let expected = BillableEntry {
+            id: 1,
             project_id: MOCK_PROJECT_ID.to_string(),
             quantity: 8.0,
             task: expected_task.to_string(),
             date: expected_date.to_string()
         };
-        let service = ProjectDataService::new(DB_FILE.to_string());
-        service.add_project(project_name, 80, Day);
-        service.add_task(project_name, expected_task);
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, expected_task).unwrap();
 
         let _ = service.add_billable_entry(project_name, expected_task, 8.0, Some(expected_date));
         let _ = service.add_billable_entry(project_name, unexpected_task, 8.0, Some(expected_date));
 
-        let _ = service.db.read(|db| {
-            assert_eq!(db.billable[0], expected);
-            assert_eq!(db.billable.len(), 1);
-        });
+        let billable = service.store.get_entries(&MOCK_PROJECT_ID.to_string()).unwrap();
+        assert_eq!(billable[0], expected);
+        assert_eq!(billable.len(), 1);
     }
     #[test]
     fn test_add_billable_entry_default_to_current_date() {
@@ -236,44 +726,45 @@ mod test {
         let task_name = &EXPECTED_TASK_NAME.to_string();
 
         let expected_date_str_substring = Local::now().to_string()[..10].to_string();
-        let service = ProjectDataService::new(DB_FILE.to_string());
-        service.add_project(project_name, 80, Day);
-        service.add_task(project_name, task_name);
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, task_name).unwrap();
 
         let _ = service.add_billable_entry(project_name, task_name, 8.0, None);
 
-        let _ = service.db.read(|db| {
-            assert_eq!(db.billable[0].date.starts_with(&expected_date_str_substring), true)
-        });
+        let billable = service.store.get_entries(&MOCK_PROJECT_ID.to_string()).unwrap();
+        assert_eq!(billable[0].date.starts_with(&expected_date_str_substring), true);
     }
 
     #[test]
-    fn test_get_monthly_billing_current_month() {
+    fn test_get_unbilled_until_today() {
         let project_name = &MOCK_PROJECT_NAME.to_string();
         let task_name = &EXPECTED_TASK_NAME.to_string();
 
         let expected_date1 = "2020-10-11 22:09:24.269707 +02:00".parse::<DateTime<Local>>().unwrap();
         let expected_date2 = "2020-10-12 22:09:24.269707 +02:00".parse::<DateTime<Local>>().unwrap();
         let expected_date3 = "2020-09-12 22:09:24.269707 +02:00".parse::<DateTime<Local>>().unwrap();
-        let service = ProjectDataService::new(DB_FILE.to_string());
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
 
 
-        service.add_project(project_name, 80, Day);
-        service.add_task(project_name, task_name);
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, task_name).unwrap();
 
         let _ = service.add_billable_entry(project_name, task_name, 8.0, Some(expected_date1));
         let _ = service.add_billable_entry(project_name, task_name, 7.0, Some(expected_date2));
         let _ = service.add_billable_entry(project_name, task_name, 1.0, Some(expected_date3));
 
-        let res = service.get_monthly_billing(project_name,  None).unwrap();
+        let until_today = BillingFilter { until: Some(Local::now().date().naive_local()), ..BillingFilter::default() };
+        let res = service.get_unbilled(project_name, &until_today).unwrap();
 
-        assert_eq!(res.len(), 2);
-        assert_eq!(res[0], Billable { project_id: MOCK_PROJECT_ID.to_string(), task: task_name.to_string(), quantity: 8.0, date: "2020-10-11T22:09:24.269707+02:00".parse::<DateTime<Local>>().unwrap() });
-        assert_eq!(res[1], Billable { project_id: MOCK_PROJECT_ID.to_string(), task: task_name.to_string(), quantity: 7.0, date: "2020-10-12T22:09:24.269707+02:00".parse::<DateTime<Local>>().unwrap() });
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[0], Billable { id: 3, project_id: MOCK_PROJECT_ID.to_string(), task: task_name.to_string(), quantity: 1.0, date: expected_date3 });
+        assert_eq!(res[1], Billable { id: 1, project_id: MOCK_PROJECT_ID.to_string(), task: task_name.to_string(), quantity: 8.0, date: "2020-10-11T22:09:24.269707+02:00".parse::<DateTime<Local>>().unwrap() });
+        assert_eq!(res[2], Billable { id: 2, project_id: MOCK_PROJECT_ID.to_string(), task: task_name.to_string(), quantity: 7.0, date: "2020-10-12T22:09:24.269707+02:00".parse::<DateTime<Local>>().unwrap() });
     }
 
     #[test]
-    fn test_get_monthly_billing_explicit_month() {
+    fn test_get_unbilled_explicit_month() {
         let project_name = &MOCK_PROJECT_NAME.to_string();
         let task_name = &EXPECTED_TASK_NAME.to_string();
 
@@ -281,21 +772,403 @@ mod test {
         let expected_date2 = "2020-10-12 22:09:24.269707 +02:00".parse::<DateTime<Local>>().unwrap();
         let expected_date3 = "2020-09-12 22:09:24.269707 +02:00".parse::<DateTime<Local>>().unwrap();
 
-        let taget_date =  "2020-09-10 22:09:24.269707 +02:00".parse::<DateTime<Local>>().unwrap();
-        let service = ProjectDataService::new(DB_FILE.to_string());
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
 
 
-        service.add_project(project_name, 80, Day);
-        service.add_task(project_name, task_name);
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, task_name).unwrap();
 
         let _ = service.add_billable_entry(project_name, task_name, 8.0, Some(expected_date1));
         let _ = service.add_billable_entry(project_name, task_name, 7.0, Some(expected_date2));
         let _ = service.add_billable_entry(project_name, task_name, 1.0, Some(expected_date3));
 
-        let res = service.get_monthly_billing(project_name,  Some(taget_date)).unwrap();
+        let month = BillingFilter { from: Some(NaiveDate::from_ymd(2020, 9, 1)), until: Some(NaiveDate::from_ymd(2020, 9, 30)), tasks: vec![] };
+        let res = service.get_unbilled(project_name, &month).unwrap();
 
 
         assert_eq!(res.len(), 1);
-        assert_eq!(res[0], Billable { project_id: MOCK_PROJECT_ID.to_string(), task: task_name.to_string(), quantity: 1.0, date: expected_date3 });
+        assert_eq!(res[0], Billable { id: 3, project_id: MOCK_PROJECT_ID.to_string(), task: task_name.to_string(), quantity: 1.0, date: expected_date3 });
+    }
+
+    #[test]
+    fn test_get_unbilled_filters_by_range_and_task() {
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let task_name = &EXPECTED_TASK_NAME.to_string();
+        let other_task = &"review".to_string();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, task_name).unwrap();
+        service.add_task(project_name, other_task).unwrap();
+        for (task, date) in [(task_name, "2020-10-11"), (task_name, "2020-09-30"), (other_task, "2020-09-12"), (task_name, "2020-08-31")].iter() {
+            let date = format!("{} 12:00:00 +02:00", date).parse::<DateTime<Local>>().unwrap();
+            service.add_billable_entry(project_name, task, 1.0, Some(date)).unwrap();
+        }
+        let ids = |filter: BillingFilter| service.get_unbilled(project_name, &filter).unwrap().iter().map(|b| b.id).collect::<Vec<u64>>();
+
+        assert_eq!(ids(BillingFilter::default()), vec![4, 3, 2, 1]);
+        assert_eq!(ids(BillingFilter { until: Some(NaiveDate::from_ymd(2020, 9, 30)), ..BillingFilter::default() }), vec![4, 3, 2]);
+        assert_eq!(ids(BillingFilter { from: Some(NaiveDate::from_ymd(2020, 9, 1)), until: Some(NaiveDate::from_ymd(2020, 9, 30)), tasks: vec![] }), vec![3, 2]);
+        assert_eq!(ids(BillingFilter { tasks: vec![task_name.to_string()], from: Some(NaiveDate::from_ymd(2020, 9, 1)), until: None }), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_changes_are_recorded() {
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let task_name = &EXPECTED_TASK_NAME.to_string();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, task_name).unwrap();
+        service.add_task(project_name, task_name).unwrap();
+        service.add_billable_entry(project_name, task_name, 8.0, None).unwrap();
+        let _ = service.add_billable_entry(project_name, &UNEXPECTED_TASK_NAME.to_string(), 8.0, None);
+
+        let operations: Vec<Operation> = service.pending_events.borrow().iter().map(|e| e.operation.clone()).collect();
+        assert_eq!(operations, vec![Operation::AddProject, Operation::AddTask, Operation::AddBillableEntry]);
+    }
+
+    #[test]
+    fn test_recorded_changes_replay_to_same_data() {
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let task_name = &EXPECTED_TASK_NAME.to_string();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, task_name).unwrap();
+        service.select_project(project_name).unwrap();
+        service.add_billable_entry(project_name, task_name, 8.0, None).unwrap();
+
+        let replayed = replay_pending(&service);
+        assert_eq!(replayed, service.store.snapshot().unwrap());
+    }
+
+    #[test]
+    fn test_undo_reverts_latest_saved_operation() {
+        let path = env::temp_dir().join(format!("hours-undo-{}.jsonl", std::process::id())).to_string_lossy().to_string();
+        let _ = std::fs::remove_file(&path);
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let task_name = &EXPECTED_TASK_NAME.to_string();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())))
+            .with_audit_log(AuditLogService::new(path.clone())).unwrap();
+
+        service.add_project(project_name, 80, Day).unwrap();
+        service.select_project(project_name).unwrap();
+        service.write_to_file().unwrap();
+        let before_work = service.store.snapshot().unwrap();
+        service.add_task(project_name, task_name).unwrap();
+        service.add_billable_entry(project_name, task_name, 8.0, None).unwrap();
+        service.write_to_file().unwrap();
+
+        let undone = service.undo().unwrap().unwrap();
+        service.write_to_file().unwrap();
+
+        assert_eq!(undone.len(), 2);
+        // The id of the undone entry is not given out again
+        assert_eq!(service.store.snapshot().unwrap(), ProjectData { last_entry_id: 1, ..before_work });
+
+        service.undo().unwrap().unwrap();
+        service.write_to_file().unwrap();
+
+        let emptied = ProjectData { last_entry_id: 1, ..ProjectData::default() };
+        assert_eq!(service.store.snapshot().unwrap(), emptied);
+        assert_eq!(service.undo().unwrap(), None);
+        let events = AuditLogService::new(path.clone()).read().unwrap();
+        assert_eq!(AuditLogService::replay(&events).unwrap(), emptied);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_repair_fixable_problems() {
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let task_name = &EXPECTED_TASK_NAME.to_string();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, task_name).unwrap();
+        service.add_billable_entry(project_name, task_name, 8.0, None).unwrap();
+        let expected = service.snapshot().unwrap();
+
+        let mut broken = expected.clone();
+        broken.billable.push(broken.billable[0].clone());
+        broken.billable.push(BillableEntry { task: "review".to_string(), ..broken.billable[0].clone() });
+        broken.selected_project = Some("bar".to_string());
+        service.store.replace(broken).unwrap();
+
+        for issue in IntegrityService::check(&service.snapshot().unwrap()) {
+            assert_eq!(service.repair(&issue.problem).unwrap(), true);
+        }
+
+        let mut repaired = service.snapshot().unwrap();
+        assert_eq!(IntegrityService::check(&repaired), vec![]);
+        assert_eq!(repaired.projects[MOCK_PROJECT_ID].tasks.contains("review"), true);
+        repaired.billable.pop();
+        repaired.projects = expected.projects.clone();
+        assert_eq!(repaired, expected);
+    }
+
+    #[test]
+    fn test_repair_keeps_billed_duplicates() {
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let task_name = &EXPECTED_TASK_NAME.to_string();
+        let date = "2020-10-11 22:09:24.269707 +02:00".parse::<DateTime<Local>>().unwrap();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, task_name).unwrap();
+        service.add_billable_entry(project_name, task_name, 8.0, Some(date)).unwrap();
+        service.add_billable_entry(project_name, task_name, 8.0, Some(date)).unwrap();
+        let project = service.get_project(project_name).unwrap().unwrap();
+        let billing = service.get_unbilled(project_name, &BillingFilter::default()).unwrap();
+        service.add_invoice(InvoiceService::invoice("RE-2020-0001", 19.0, &MOCK_PROJECT_ID.to_string(), &project, &billing, date.date().naive_local(), None)).unwrap();
+
+        let issues = IntegrityService::check(&service.snapshot().unwrap());
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].problem.needs_confirmation(), true);
+        assert_eq!(service.repair(&issues[0].problem).unwrap(), false);
+        assert_eq!(service.snapshot().unwrap().billable.len(), 2);
+    }
+
+    #[test]
+    fn test_project_ids_are_unicode_aware_slugs() {
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+
+        service.add_project(&"Café GmbH".to_string(), 80, Day).unwrap();
+
+        assert_eq!(service.get_projects().unwrap()[0].0, "cafe-gmbh");
+        assert_eq!(service.get_project(&"CAFÉ GMBH".to_string()).unwrap().map(|p| p.name), Some("Café GmbH".to_string()));
+        assert_eq!(service.add_project(&"café gmbh".to_string(), 80, Day).is_err(), true);
+    }
+
+    #[test]
+    fn test_rename_keeps_entries() {
+        let task_name = &EXPECTED_TASK_NAME.to_string();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(&MOCK_PROJECT_NAME.to_string(), 80, Day).unwrap();
+        service.add_task(&MOCK_PROJECT_NAME.to_string(), task_name).unwrap();
+        service.add_billable_entry(&MOCK_PROJECT_NAME.to_string(), task_name, 8.0, None).unwrap();
+
+        service.rename_project(&MOCK_PROJECT_NAME.to_string(), &"Bar Inc".to_string()).unwrap();
+
+        assert_eq!(service.get_project(&MOCK_PROJECT_NAME.to_string()).unwrap().map(|p| p.name), Some("Bar Inc".to_string()));
+        assert_eq!(service.get_unbilled(&"bar inc".to_string(), &BillingFilter::default()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_aliases() {
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(&MOCK_PROJECT_NAME.to_string(), 80, Day).unwrap();
+        service.add_project(&"Bar".to_string(), 80, Day).unwrap();
+
+        service.add_alias(&MOCK_PROJECT_NAME.to_string(), &"F".to_string()).unwrap();
+
+        assert_eq!(service.get_project(&"f".to_string()).unwrap().map(|p| p.name), Some(MOCK_PROJECT_NAME.to_string()));
+        assert_eq!(service.add_alias(&"Bar".to_string(), &"f".to_string()).is_err(), true);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_add_project_with_taken_slug() {
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(&MOCK_PROJECT_NAME.to_string(), 80, Day).unwrap();
+        service.rename_project(&MOCK_PROJECT_NAME.to_string(), &"Bar".to_string()).unwrap();
+
+        service.add_project(&MOCK_PROJECT_NAME.to_string(), 90, Day).unwrap();
+        service.add_project(&"Foo!".to_string(), 90, Day).unwrap_err();
+
+        assert_eq!(service.resolve_project_id(&MOCK_PROJECT_NAME.to_string()).unwrap(), "foo-2");
+        assert_eq!(service.resolve_project_id(&"Bar".to_string()).unwrap(), MOCK_PROJECT_ID);
+    }
+
+    #[test]
+    fn test_resolve_project_prefix() {
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(&"Foobar".to_string(), 80, Day).unwrap();
+        service.add_project(&"Football".to_string(), 80, Day).unwrap();
+
+        assert_eq!(service.resolve_project_id(&"foob".to_string()).unwrap(), "foobar");
+        assert_eq!(service.resolve_project_id(&"foo".to_string()).is_err(), true);
+        assert_eq!(
+            service.resolve_project_id(&"fotball".to_string()).unwrap_err().to_string(),
+            "Unknown project fotball, did you mean football?"
+        );
+    }
+
+    #[test]
+    fn test_resolve_task() {
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, &"development".to_string()).unwrap();
+        service.add_task(project_name, &"devops".to_string()).unwrap();
+
+        assert_eq!(service.resolve_task(project_name, &"deve".to_string(), false).unwrap(), "development");
+        assert_eq!(service.resolve_task(project_name, &"review".to_string(), false).is_err(), true);
+        assert_eq!(service.resolve_task(project_name, &"dev".to_string(), false).is_err(), true);
+        assert_eq!(service.resolve_task(project_name, &"devlopment".to_string(), false).is_err(), true);
+        assert_eq!(service.resolve_task(project_name, &"devlopment".to_string(), true).unwrap(), "devlopment");
+    }
+
+    #[test]
+    fn test_invoice_numbers() {
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        let numbering = InvoiceNumbering { pattern: "RE-{YYYY}-{seq:04}".to_string(), yearly_reset: true };
+
+        assert_eq!(service.issue_invoice_number(&numbering, NaiveDate::from_ymd(2020, 12, 30)).unwrap(), "RE-2020-0001");
+        assert_eq!(service.issue_invoice_number(&numbering, NaiveDate::from_ymd(2020, 12, 31)).unwrap(), "RE-2020-0002");
+        service.record_invoice_number(&"RE-2021-0001".to_string()).unwrap();
+        assert_eq!(service.record_invoice_number(&"RE-2020-0002".to_string()).is_err(), true);
+
+        assert_eq!(service.peek_invoice_number(&numbering, NaiveDate::from_ymd(2021, 1, 4)).unwrap(), "RE-2021-0002");
+        assert_eq!(service.snapshot().unwrap().invoice_numbers.used.len(), 3);
+        assert_eq!(service.issue_invoice_number(&numbering, NaiveDate::from_ymd(2021, 1, 4)).unwrap(), "RE-2021-0002");
+        assert_eq!(service.snapshot().unwrap().invoice_numbers.used.len(), 4);
+    }
+
+    #[test]
+    fn test_undo_stops_at_issued_invoice_number() {
+        let path = env::temp_dir().join(format!("hours-undo-invoice-{}.jsonl", std::process::id())).to_string_lossy().to_string();
+        let _ = std::fs::remove_file(&path);
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())))
+            .with_audit_log(AuditLogService::new(path.clone())).unwrap();
+
+        service.add_project(&MOCK_PROJECT_NAME.to_string(), 80, Day).unwrap();
+        service.write_to_file().unwrap();
+        service.issue_invoice_number(&InvoiceNumbering::default(), NaiveDate::from_ymd(2020, 12, 30)).unwrap();
+        service.write_to_file().unwrap();
+
+        assert_eq!(service.undo().unwrap(), None);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_invoiced_entries_are_not_billed_again() {
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let task_name = &EXPECTED_TASK_NAME.to_string();
+        let date = "2020-10-11 22:09:24.269707 +02:00".parse::<DateTime<Local>>().unwrap();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, task_name).unwrap();
+        service.add_billable_entry(project_name, task_name, 8.0, Some(date)).unwrap();
+        service.add_billable_entry(project_name, task_name, 7.0, Some(date)).unwrap();
+
+        let project = service.get_project(project_name).unwrap().unwrap();
+        let billing = service.get_unbilled(project_name, &BillingFilter::default()).unwrap();
+        let invoice = InvoiceService::invoice("RE-2020-0001", 19.0, &MOCK_PROJECT_ID.to_string(), &project, &billing[..1], date.date().naive_local(), None);
+        service.add_invoice(invoice.clone()).unwrap();
+
+        let remaining = service.get_unbilled(project_name, &BillingFilter::default()).unwrap();
+        assert_eq!(remaining.iter().map(|b| b.id).collect::<Vec<u64>>(), vec![2]);
+        assert_eq!(service.get_invoice(&"RE-2020-0001".to_string()).unwrap(), invoice);
+        assert_eq!(service.get_invoice(&"RE-2020-0002".to_string()).is_err(), true);
+
+        let replayed = replay_pending(&service);
+        assert_eq!(replayed, service.store.snapshot().unwrap());
+    }
+
+    #[test]
+    fn test_cancel_invoice_releases_entries() {
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let task_name = &EXPECTED_TASK_NAME.to_string();
+        let date = "2020-10-11 22:09:24.269707 +02:00".parse::<DateTime<Local>>().unwrap();
+        let numbering = InvoiceNumbering::default();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        service.add_task(project_name, task_name).unwrap();
+        service.add_billable_entry(project_name, task_name, 8.0, Some(date)).unwrap();
+        let project = service.get_project(project_name).unwrap().unwrap();
+        let billing = service.get_unbilled(project_name, &BillingFilter::default()).unwrap();
+        let number = service.issue_invoice_number(&numbering, NaiveDate::from_ymd(2020, 10, 31)).unwrap();
+        service.add_invoice(InvoiceService::invoice(&number, 19.0, &MOCK_PROJECT_ID.to_string(), &project, &billing, NaiveDate::from_ymd(2020, 10, 31), None)).unwrap();
+
+        let cancellation = service.cancel_invoice(&number, &numbering, NaiveDate::from_ymd(2020, 11, 2)).unwrap();
+
+        assert_eq!(cancellation.number, "RE-2020-0002");
+        assert_eq!(service.get_invoice(&number).unwrap().status, InvoiceStatus::Cancelled);
+        assert_eq!(service.get_unbilled(project_name, &BillingFilter::default()).unwrap().len(), 1);
+        assert_eq!(service.cancel_invoice(&number, &numbering, NaiveDate::from_ymd(2020, 11, 2)).is_err(), true);
+        assert_eq!(service.cancel_invoice(&cancellation.number, &numbering, NaiveDate::from_ymd(2020, 11, 2)).is_err(), true);
+        assert_eq!(service.mark_paid(&number).is_err(), true);
+        assert_eq!(service.mark_paid(&cancellation.number).is_err(), true);
+
+        let replayed = replay_pending(&service);
+        assert_eq!(replayed, service.store.snapshot().unwrap());
+    }
+
+    #[test]
+    fn test_invoice_submissions() {
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let date = NaiveDate::from_ymd(2020, 10, 31);
+        let numbering = InvoiceNumbering::default();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        let project = service.get_project(project_name).unwrap().unwrap();
+        let mut invoice = InvoiceService::invoice("RE-2020-0001", 19.0, &MOCK_PROJECT_ID.to_string(), &project, &[], date, None);
+        service.add_invoice(invoice.clone()).unwrap();
+        invoice.number = "RE-2020-0002".to_string();
+        invoice.submission = Some(Submission { queued: "2020-10-31 10:00:00 +01:00".to_string(), attempts: 0, last_error: None, rejected: false });
+        service.add_invoice(invoice.clone()).unwrap();
+        let number = &invoice.number;
+
+        assert_eq!(service.get_queued_invoices().unwrap().len(), 1);
+        assert_eq!(service.record_submission_attempt(&"RE-2020-0001".to_string()).is_err(), true);
+        assert_eq!(service.record_submission_attempt(number).unwrap().attempts, 0);
+        service.record_submission_failure(number, &"offline".to_string(), false).unwrap();
+        assert_eq!(service.cancel_invoice(number, &numbering, date).is_err(), true);
+        let submission = service.record_submission_attempt(number).unwrap();
+        assert_eq!((submission.attempts, submission.last_error), (1, Some("offline".to_string())));
+        assert_eq!(service.get_invoice(number).unwrap().status, InvoiceStatus::Draft);
+
+        service.complete_submission(number, &"voucher-id".to_string(), Some(&"RE0001".to_string())).unwrap();
+
+        let invoice = service.get_invoice(&"RE0001".to_string()).unwrap();
+        assert_eq!(&invoice.number, number);
+        assert_eq!((invoice.lex_office_id, invoice.lex_office_number, invoice.submission), (Some("voucher-id".to_string()), Some("RE0001".to_string()), None));
+        assert_eq!(invoice.status, InvoiceStatus::Sent);
+        service.mark_paid(number).unwrap();
+        assert_eq!(service.get_invoice(number).unwrap().status, InvoiceStatus::Paid);
+        assert_eq!(service.mark_paid(number).is_err(), true);
+        assert_eq!(service.get_queued_invoices().unwrap().len(), 0);
+        let replayed = replay_pending(&service);
+        assert_eq!(replayed, service.store.snapshot().unwrap());
+    }
+
+    #[test]
+    fn test_lex_office_invoices_leave_the_sequence_alone() {
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let date = NaiveDate::from_ymd(2020, 10, 31);
+        let numbering = InvoiceNumbering::default();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        let project = service.get_project(project_name).unwrap().unwrap();
+
+        let reference = service.lex_office_reference(date).unwrap();
+        let mut invoice = InvoiceService::invoice(&reference, 19.0, &MOCK_PROJECT_ID.to_string(), &project, &[], date, None);
+        invoice.lex_office_id = Some("voucher-id".to_string());
+        service.add_invoice(invoice).unwrap();
+        let cancellation = service.cancel_invoice(&reference, &numbering, date).unwrap();
+
+        assert_eq!(reference, "LX-2020-10-31-1");
+        assert_eq!(cancellation.number, "LX-2020-10-31-2");
+        assert_eq!(service.peek_invoice_number(&numbering, date).unwrap(), "RE-2020-0001");
+    }
+
+    #[test]
+    fn test_rejected_submission_can_be_cancelled() {
+        let project_name = &MOCK_PROJECT_NAME.to_string();
+        let date = NaiveDate::from_ymd(2020, 10, 31);
+        let numbering = InvoiceNumbering::default();
+        let service = ProjectDataService::new(Box::new(MemoryStore::new(ProjectData::default())));
+        service.add_project(project_name, 80, Day).unwrap();
+        let project = service.get_project(project_name).unwrap().unwrap();
+        let mut invoice = InvoiceService::invoice("RE-2020-0001", 19.0, &MOCK_PROJECT_ID.to_string(), &project, &[], date, None);
+        invoice.submission = Some(Submission { queued: "2020-10-31 10:00:00 +01:00".to_string(), attempts: 0, last_error: None, rejected: false });
+        service.add_invoice(invoice.clone()).unwrap();
+        let number = &invoice.number;
+
+        service.record_submission_attempt(number).unwrap();
+        service.record_submission_failure(number, &"invalid address".to_string(), true).unwrap();
+
+        assert_eq!(service.get_queued_invoices().unwrap().len(), 0);
+        let cancellation = service.cancel_invoice(number, &numbering, date).unwrap();
+        assert_eq!(cancellation.cancels, Some(number.to_string()));
+        assert_eq!(service.get_invoice(number).unwrap().submission, None);
+    }
+}
@@ -0,0 +1,75 @@
+use fs2::FileExt;
+use std::fs::{File, OpenOptions};
+use std::thread;
+use std::time::{Duration, Instant};
+use snafu::ResultExt;
+
+use crate::domain::errors::project_data::{ProjectDataError, Locked, LockFile};
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+
+/// Exclusive advisory lock on `<path>.lock`, released when dropped.
+pub struct FileLock {
+    _file: File
+}
+
+impl FileLock {
+    /// Waits up to `timeout` for other processes to release the lock.
+    pub fn acquire(path: &String, timeout: Duration) -> Result<FileLock, ProjectDataError> {
+        let lock_path = format!("{}.lock", path);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .context(LockFile { path: &lock_path })?;
+        let started = Instant::now();
+
+        while file.try_lock_exclusive().is_err() {
+            if started.elapsed() >= timeout {
+                return Locked { path }.fail()
+            }
+            thread::sleep(RETRY_INTERVAL);
+        }
+
+        return Ok(FileLock {
+            _file: file
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    fn temp_path(name: &str) -> String {
+        return env::temp_dir()
+            .join(format!("hours-lock-{}-{}", std::process::id(), name))
+            .to_string_lossy()
+            .to_string();
+    }
+
+    #[test]
+    fn test_second_lock_fails() {
+        let path = temp_path("second");
+
+        let first = FileLock::acquire(&path, Duration::from_millis(0)).unwrap();
+        let second = FileLock::acquire(&path, Duration::from_millis(200));
+
+        assert_eq!(second.is_err(), true);
+        drop(first);
+    }
+
+    #[test]
+    fn test_lock_released_on_drop() {
+        let path = temp_path("released");
+
+        let first = FileLock::acquire(&path, Duration::from_millis(0)).unwrap();
+        drop(first);
+        let second = FileLock::acquire(&path, Duration::from_millis(0));
+
+        assert_eq!(second.is_ok(), true);
+    }
+}
@@ -14,7 +14,7 @@ impl FilePathService {
         }
         let home_path = env::var(HOME_ENV_KEY).context(EnvVariableError {variable: HOME_ENV_KEY.to_string()})?;
 
-        return Ok(format!("{}{}", home_path, path))
+        return Ok(format!("{}{}", home_path, &path[1..]))
     }
 }
 
@@ -32,6 +32,7 @@ mod test {
 
         assert_eq!(res.contains(expected), true);
         assert_eq!(res.len() > expected.len(), true);
+        assert_eq!(res.contains("~"), false);
     }
 
     #[test]
@@ -12,6 +12,7 @@ use crate::domain::errors::toml_file::WriteFile;
 pub trait FileServiceTrait {
     fn new() -> Self;
     fn read_file_to_string(&self, file_name: &String) -> Result<String, FileError>;
+    #[allow(dead_code)]
     fn write_file_from_string(&self, file_name: &String, content: String) -> Result<(), FileError>;
 }
 
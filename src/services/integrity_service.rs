@@ -0,0 +1,98 @@
+use chrono::{DateTime, Local};
+
+use crate::domain::objects::integrity::{Issue, Problem};
+use crate::domain::objects::project_data::ProjectData;
+
+
+/// Finds inconsistencies that hand edits of the data file can introduce.
+pub struct IntegrityService {}
+
+impl IntegrityService {
+    pub fn check(data: &ProjectData) -> Vec<Issue> {
+        let mut issues = vec![];
+
+        if let Some(project_id) = &data.selected_project {
+            if !data.projects.contains_key(project_id) {
+                issues.push(Issue {
+                    location: "selected_project".to_string(),
+                    problem: Problem::UnknownSelectedProject { project_id: project_id.to_string() }
+                });
+            }
+        }
+
+        for (index, entry) in data.billable.iter().enumerate() {
+            let location = format!("billable[{}]", index);
+            let mut problems = vec![];
+
+            match data.projects.get(&entry.project_id) {
+                None => problems.push(Problem::UnknownProject { project_id: entry.project_id.to_string() }),
+                Some(project) if !project.tasks.contains(&entry.task) =>
+                    problems.push(Problem::UnknownTask { project_id: entry.project_id.to_string(), task: entry.task.to_string() }),
+                Some(_) => {}
+            }
+            if entry.date.parse::<DateTime<Local>>().is_err() {
+                problems.push(Problem::InvalidDate { date: entry.date.to_string() });
+            }
+            if entry.quantity < 0.0 {
+                problems.push(Problem::NegativeQuantity { quantity: entry.quantity });
+            }
+            if data.billable[..index].iter().any(|e| e.same_work(entry)) {
+                problems.push(Problem::DuplicateEntry { entry: entry.clone() });
+            }
+
+            issues.extend(problems.into_iter().map(|problem| Issue { location: location.to_string(), problem }));
+        }
+
+        return issues;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::domain::objects::project_data::{BillableEntry, BillableUnit, LineGrouping, Project};
+
+    fn entry(id: u64, project_id: &str, task: &str, quantity: f32, date: &str) -> BillableEntry {
+        return BillableEntry {
+            id,
+            project_id: project_id.to_string(),
+            task: task.to_string(),
+            quantity,
+            date: date.to_string()
+        }
+    }
+
+    #[test]
+    fn test_check_finds_all_problems() {
+        const DATE: &str = "2020-10-11 22:09:24.269707 +02:00";
+        let mut tasks = HashSet::new();
+        tasks.insert("development".to_string());
+        let mut data = ProjectData::default();
+        data.projects.insert("foo".to_string(), Project { name: "Foo".to_string(), unit_price: 80, unit: BillableUnit::Hour, tasks, aliases: vec![], address: vec![], grouping: LineGrouping::Task });
+        data.selected_project = Some("bar".to_string());
+        data.billable = vec![
+            entry(1, "foo", "development", 8.0, DATE),
+            entry(2, "bar", "development", 8.0, DATE),
+            entry(3, "foo", "testing", 8.0, DATE),
+            entry(4, "foo", "development", -1.0, "yesterday"),
+            entry(5, "foo", "development", 8.0, DATE)
+        ];
+
+        let issues: Vec<String> = IntegrityService::check(&data).iter().map(|i| i.to_string()).collect();
+
+        assert_eq!(issues, vec![
+            "selected_project: selected project bar does not exist",
+            "billable[1]: entry of unknown project bar",
+            "billable[2]: task testing is missing from project foo",
+            "billable[3]: invalid date yesterday",
+            "billable[3]: negative quantity -1",
+            "billable[4]: duplicate of an earlier entry"
+        ]);
+    }
+
+    #[test]
+    fn test_check_consistent_data() {
+        assert_eq!(IntegrityService::check(&ProjectData::default()), vec![]);
+    }
+}
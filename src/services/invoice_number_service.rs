@@ -0,0 +1,129 @@
+use chrono::{Datelike, NaiveDate};
+
+use crate::domain::errors::project_data::{InvalidNumberPattern, ProjectDataError};
+use crate::domain::objects::InvoiceNumbering;
+use crate::domain::objects::project_data::InvoiceSequence;
+
+
+enum Part {
+    Text(String),
+    Year,
+    ShortYear,
+    Month,
+    Day,
+    /// Sequence number, zero padded to the width
+    Sequence(usize)
+}
+
+pub struct InvoiceNumberService;
+
+impl InvoiceNumberService {
+    /// Formats invoice number `sequence` issued on `date`. Patterns contain `{seq}` or `{seq:04}` for the zero padded
+    /// sequence number, and can contain `{YYYY}`, `{YY}`, `{MM}` and `{DD}` of the date.
+    pub fn format(numbering: &InvoiceNumbering, date: NaiveDate, sequence: u32) -> Result<String, ProjectDataError> {
+        let parts = InvoiceNumberService::parse(numbering)?;
+
+        return Ok(parts.iter().map(|part| match part {
+            Part::Text(text) => text.to_string(),
+            Part::Year => format!("{:04}", date.year()),
+            Part::ShortYear => format!("{:02}", date.year() % 100),
+            Part::Month => format!("{:02}", date.month()),
+            Part::Day => format!("{:02}", date.day()),
+            Part::Sequence(width) => format!("{:0width$}", sequence, width = width)
+        }).collect());
+    }
+
+    /// Sequence number following `sequence` for a number issued in `year`.
+    pub fn next(sequence: &InvoiceSequence, numbering: &InvoiceNumbering, year: i32) -> u32 {
+        return match numbering.yearly_reset && sequence.year != Some(year) {
+            true => 1,
+            false => sequence.last + 1
+        }
+    }
+
+    /// Sequence number and invoice number to issue next on `date`, skipping numbers already in use.
+    pub fn next_unused(sequence: &InvoiceSequence, numbering: &InvoiceNumbering, date: NaiveDate) -> Result<(u32, String), ProjectDataError> {
+        let mut next = InvoiceNumberService::next(sequence, numbering, date.year());
+        let mut number = InvoiceNumberService::format(numbering, date, next)?;
+        while sequence.used.contains(&number) {
+            next += 1;
+            number = InvoiceNumberService::format(numbering, date, next)?;
+        }
+
+        return Ok((next, number));
+    }
+
+    fn parse(numbering: &InvoiceNumbering) -> Result<Vec<Part>, ProjectDataError> {
+        let pattern = &numbering.pattern;
+        let invalid = |reason: String| InvalidNumberPattern { pattern, reason }.fail();
+
+        let mut parts = vec![];
+        let mut rest = pattern.as_str();
+        while let Some(start) = rest.find('{') {
+            parts.push(Part::Text(rest[..start].to_string()));
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => return invalid("unclosed {".to_string())
+            };
+            parts.push(match &rest[start + 1..end] {
+                "YYYY" => Part::Year,
+                "YY" => Part::ShortYear,
+                "MM" => Part::Month,
+                "DD" => Part::Day,
+                "seq" => Part::Sequence(0),
+                placeholder => match placeholder.strip_prefix("seq:").and_then(|width| width.parse::<usize>().ok()) {
+                    Some(width) => Part::Sequence(width),
+                    None => return invalid(format!("unknown placeholder {{{}}}", placeholder))
+                }
+            });
+            rest = &rest[end + 1..];
+        }
+        parts.push(Part::Text(rest.to_string()));
+
+        if !parts.iter().any(|part| matches!(part, Part::Sequence(_))) {
+            return invalid("it needs {seq} to make numbers unique".to_string())
+        }
+        if numbering.yearly_reset && !parts.iter().any(|part| matches!(part, Part::Year | Part::ShortYear)) {
+            return invalid("numbers that start over every year need {YYYY} or {YY}".to_string())
+        }
+
+        return Ok(parts);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn numbering(pattern: &str, yearly_reset: bool) -> InvoiceNumbering {
+        return InvoiceNumbering { pattern: pattern.to_string(), yearly_reset };
+    }
+
+    #[test]
+    fn test_format() {
+        let date = NaiveDate::from_ymd(2020, 3, 7);
+
+        assert_eq!(InvoiceNumberService::format(&numbering("RE-{YYYY}-{seq:04}", false), date, 12).unwrap(), "RE-2020-0012");
+        assert_eq!(InvoiceNumberService::format(&numbering("{YY}{MM}{DD}/{seq}", false), date, 12).unwrap(), "200307/12");
+        assert_eq!(InvoiceNumberService::format(&numbering("{seq:2}", false), date, 123).unwrap(), "123");
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        let date = NaiveDate::from_ymd(2020, 3, 7);
+
+        assert_eq!(InvoiceNumberService::format(&numbering("RE-{YYYY}", false), date, 1).is_err(), true);
+        assert_eq!(InvoiceNumberService::format(&numbering("RE-{seq", false), date, 1).is_err(), true);
+        assert_eq!(InvoiceNumberService::format(&numbering("RE-{year}-{seq}", false), date, 1).is_err(), true);
+        assert_eq!(InvoiceNumberService::format(&numbering("RE-{seq}", true), date, 1).is_err(), true);
+    }
+
+    #[test]
+    fn test_next() {
+        let sequence = InvoiceSequence { last: 7, year: Some(2020), used: vec![] };
+
+        assert_eq!(InvoiceNumberService::next(&sequence, &numbering("{seq}", false), 2021), 8);
+        assert_eq!(InvoiceNumberService::next(&sequence, &numbering("{YYYY}{seq}", true), 2020), 8);
+        assert_eq!(InvoiceNumberService::next(&sequence, &numbering("{YYYY}{seq}", true), 2021), 1);
+    }
+}
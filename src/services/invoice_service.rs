@@ -0,0 +1,269 @@
+use chrono::NaiveDate;
+
+use crate::domain::objects::{BankDetails, Sender};
+use crate::domain::objects::invoice::{tax_of, Invoice, InvoiceDraft, InvoiceStatus, LineItem, ServicePeriod};
+use crate::domain::objects::project_data::{Billable, LineGrouping, Project, Quantity};
+
+
+pub struct InvoiceService;
+
+impl InvoiceService {
+    /// Record of the invoice `number` of `billing`, kept once the invoice is issued.
+    /// The service period is `period` if given, otherwise the days of the earliest and the latest entry.
+    pub fn invoice(number: &str, tax_rate: f32, project_id: &String, project: &Project, billing: &[Billable], date: NaiveDate, period: Option<ServicePeriod>) -> Invoice {
+        let line_items = InvoiceService::line_items(project, billing);
+        let net: i64 = line_items.iter().map(LineItem::net).sum();
+        let tax = tax_of(net, tax_rate);
+        let mut entries: Vec<u64> = billing.iter().map(|b| b.id).collect();
+        entries.sort_unstable();
+
+        return Invoice {
+            number: number.to_string(),
+            cancels: None,
+            project_id: project_id.to_string(),
+            customer: project.address.clone(),
+            date: date.format("%Y-%m-%d").to_string(),
+            service_period: period.or_else(|| InvoiceService::service_period(billing)),
+            line_items,
+            net,
+            tax_rate,
+            tax,
+            gross: net + tax,
+            status: InvoiceStatus::Draft,
+            lex_office_id: None,
+            lex_office_number: None,
+            submission: None,
+            entries
+        }
+    }
+
+    /// Cancellation `number` of `invoice`: the same line items with negated quantities, covering no entries.
+    pub fn cancellation(invoice: &Invoice, number: &str, date: NaiveDate) -> Invoice {
+        return Invoice {
+            number: number.to_string(),
+            cancels: Some(invoice.number.to_string()),
+            date: date.format("%Y-%m-%d").to_string(),
+            line_items: invoice.line_items.iter()
+                .map(|item| LineItem { quantity: -item.quantity, ..item.clone() })
+                .collect(),
+            net: -invoice.net,
+            tax: -invoice.tax,
+            gross: -invoice.gross,
+            status: InvoiceStatus::Draft,
+            lex_office_id: None,
+            lex_office_number: None,
+            submission: None,
+            entries: vec![],
+            ..invoice.clone()
+        }
+    }
+
+    /// Draft to render a stored invoice, `None` when its date is invalid.
+    pub fn draft_of(invoice: &Invoice, sender: &Sender, bank: &BankDetails) -> Option<InvoiceDraft> {
+        return Some(InvoiceDraft {
+            number: invoice.number.to_string(),
+            cancels: invoice.cancels.clone(),
+            sender: sender.clone(),
+            bank: bank.clone(),
+            customer: invoice.customer.clone(),
+            date: NaiveDate::parse_from_str(&invoice.date, "%Y-%m-%d").ok()?,
+            service_period: invoice.service_period.clone(),
+            line_items: invoice.line_items.clone(),
+            tax_rate: invoice.tax_rate
+        });
+    }
+
+    /// Days of the earliest and the latest entry, `None` without entries.
+    pub fn service_period(billing: &[Billable]) -> Option<ServicePeriod> {
+        let start = billing.iter().map(|b| b.date).min()?;
+        let end = billing.iter().map(|b| b.date).max()?;
+
+        return Some(ServicePeriod {
+            start: start.format("%Y-%m-%d").to_string(),
+            end: end.format("%Y-%m-%d").to_string()
+        });
+    }
+
+    /// Line items grouped by the project's `grouping`, tasks ordered by name and everything else by date.
+    /// Quantities are added up per line and rounded once, so that rounding errors of single entries do not add up.
+    pub fn line_items(project: &Project, billing: &[Billable]) -> Vec<LineItem> {
+        let mut entries: Vec<&Billable> = billing.iter().collect();
+        entries.sort_by_key(|b| b.date);
+
+        let mut groups: Vec<(String, Vec<&Billable>)> = vec![];
+        for (index, billable) in entries.into_iter().enumerate() {
+            let key = match project.grouping {
+                LineGrouping::Task => billable.task.to_string(),
+                LineGrouping::Day => billable.date.format("%Y-%m-%d").to_string(),
+                LineGrouping::Week => billable.date.format("%G-W%V").to_string(),
+                LineGrouping::Entry => index.to_string(),
+                LineGrouping::Single => String::new()
+            };
+            match groups.iter_mut().find(|(group_key, _)| group_key == &key) {
+                Some((_, group)) => group.push(billable),
+                None => groups.push((key, vec![billable]))
+            }
+        }
+        if project.grouping == LineGrouping::Task {
+            groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+
+        return groups.into_iter()
+            .map(|(_, group)| LineItem {
+                description: InvoiceService::description(project, &group),
+                quantity: round_quantity(group.iter().map(|b| b.quantity).sum()),
+                unit: project.unit.clone(),
+                unit_price: project.unit_price
+            })
+            .collect();
+    }
+
+    /// Description of a line of `group`, entries oldest first.
+    fn description(project: &Project, group: &[&Billable]) -> String {
+        let mut tasks: Vec<&str> = vec![];
+        for billable in group.iter() {
+            if !tasks.contains(&billable.task.as_str()) {
+                tasks.push(&billable.task);
+            }
+        }
+        let (first, last) = (group[0].date, group[group.len() - 1].date);
+
+        return match project.grouping {
+            LineGrouping::Task => tasks.join(", "),
+            LineGrouping::Day | LineGrouping::Entry => format!("{}: {}", first.format("%Y-%m-%d"), tasks.join(", ")),
+            LineGrouping::Week => format!("Week {}: {}", first.format("%V/%G"), tasks.join(", ")),
+            LineGrouping::Single if first.format("%Y-%m").to_string() == last.format("%Y-%m").to_string() =>
+                format!("{}, {}", project.name, first.format("%B %Y")),
+            LineGrouping::Single => format!("{}, {} to {}", project.name, first.format("%Y-%m-%d"), last.format("%Y-%m-%d"))
+        }
+    }
+}
+
+/// Rounds to hundredths, e.g. 0.1 + 0.2 to 0.3.
+fn round_quantity(quantity: Quantity) -> Quantity {
+    return (quantity * 100.0).round() / 100.0;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+    use chrono::{Local, TimeZone};
+    use crate::domain::objects::project_data::BillableUnit;
+
+    fn billable(task: &str, quantity: Quantity) -> Billable {
+        return Billable {
+            id: 1,
+            project_id: "foo".to_string(),
+            task: task.to_string(),
+            quantity,
+            date: Local.ymd(2020, 10, 11).and_hms(12, 0, 0)
+        }
+    }
+
+    fn project() -> Project {
+        return Project {
+            name: "Foo".to_string(),
+            unit_price: 80,
+            unit: BillableUnit::Hour,
+            tasks: HashSet::new(),
+            aliases: vec![],
+            address: vec!["Foo GmbH".to_string()],
+            grouping: LineGrouping::Task
+        };
+    }
+
+    fn draft(billing: &[Billable]) -> InvoiceDraft {
+        let sender = Sender { name: "Me".to_string(), address: vec![], tax_id: None };
+        let bank = BankDetails { account_holder: "Me".to_string(), iban: "DE00".to_string(), bic: None, bank_name: None };
+
+        let invoice = InvoiceService::invoice("RE-2020-0001", 19.0, &"foo".to_string(), &project(), billing, NaiveDate::from_ymd(2020, 10, 31), None);
+
+        return InvoiceService::draft_of(&invoice, &sender, &bank).unwrap();
+    }
+
+    #[test]
+    fn test_draft_adds_up_tasks() {
+        let invoice = draft(&[billable("review", 1.0), billable("dev", 2.5), billable("review", 0.5)]);
+
+        let items: Vec<(&str, Quantity)> = invoice.line_items.iter().map(|i| (i.description.as_str(), i.quantity)).collect();
+        assert_eq!(items, vec![("dev", 2.5), ("review", 1.5)]);
+        assert_eq!(invoice.customer, vec!["Foo GmbH".to_string()]);
+    }
+
+    #[test]
+    fn test_line_items_by_grouping() {
+        let at = |id: u64, task: &str, day: u32| Billable { id, date: Local.ymd(2020, 10, day).and_hms(9, 0, 0), ..billable(task, 1.5) };
+        let billing = vec![at(1, "review", 12), at(2, "dev", 11), at(3, "dev", 12), at(4, "dev", 20)];
+        let items = |grouping: LineGrouping| InvoiceService::line_items(&Project { grouping, ..project() }, &billing).into_iter()
+            .map(|i| (i.description, i.quantity))
+            .collect::<Vec<(String, Quantity)>>();
+
+        assert_eq!(items(LineGrouping::Task), vec![("dev".to_string(), 4.5), ("review".to_string(), 1.5)]);
+        assert_eq!(items(LineGrouping::Day), vec![
+            ("2020-10-11: dev".to_string(), 1.5),
+            ("2020-10-12: review, dev".to_string(), 3.0),
+            ("2020-10-20: dev".to_string(), 1.5)
+        ]);
+        assert_eq!(items(LineGrouping::Week), vec![("Week 41/2020: dev".to_string(), 1.5), ("Week 42/2020: review, dev".to_string(), 3.0), ("Week 43/2020: dev".to_string(), 1.5)]);
+        assert_eq!(items(LineGrouping::Entry).len(), 4);
+        assert_eq!(items(LineGrouping::Entry)[0], ("2020-10-11: dev".to_string(), 1.5));
+        assert_eq!(items(LineGrouping::Single), vec![("Foo, October 2020".to_string(), 6.0)]);
+    }
+
+    #[test]
+    fn test_line_items_round_once_per_line() {
+        let billing = vec![billable("dev", 0.1), billable("dev", 0.2), billable("dev", 1.0 / 3.0), billable("dev", 1.0 / 3.0), billable("dev", 1.0 / 3.0)];
+
+        let items = InvoiceService::line_items(&project(), &billing);
+
+        assert_eq!(items[0].quantity, 1.3);
+        assert_eq!(items[0].net(), 10400);
+    }
+
+    #[test]
+    fn test_draft_totals() {
+        let invoice = draft(&[billable("dev", 2.5), billable("review", 0.25)]);
+
+        assert_eq!(invoice.net(), 22000);
+        assert_eq!(invoice.tax(), 4180);
+        assert_eq!(invoice.gross(), 26180);
+    }
+
+    #[test]
+    fn test_invoice_record() {
+        let billing = vec![
+            Billable { id: 7, date: Local.ymd(2020, 10, 20).and_hms(9, 0, 0), ..billable("dev", 2.5) },
+            Billable { id: 3, ..billable("review", 0.25) }
+        ];
+
+        let invoice = InvoiceService::invoice("RE-2020-0001", 19.0, &"foo".to_string(), &project(), &billing, NaiveDate::from_ymd(2020, 10, 31), None);
+
+        assert_eq!(invoice.entries, vec![3, 7]);
+        assert_eq!(invoice.service_period, Some(ServicePeriod { start: "2020-10-11".to_string(), end: "2020-10-20".to_string() }));
+        assert_eq!((invoice.net, invoice.tax, invoice.gross), (22000, 4180, 26180));
+        assert_eq!(invoice.status, InvoiceStatus::Draft);
+    }
+
+    #[test]
+    fn test_invoice_with_explicit_period() {
+        let period = ServicePeriod { start: "2020-10-01".to_string(), end: "2020-10-31".to_string() };
+
+        let invoice = InvoiceService::invoice("RE-2020-0001", 19.0, &"foo".to_string(), &project(), &[billable("dev", 2.5)], NaiveDate::from_ymd(2020, 10, 31), Some(period.clone()));
+
+        assert_eq!(invoice.service_period, Some(period));
+    }
+
+    #[test]
+    fn test_cancellation_reverses_invoice() {
+        let invoice = InvoiceService::invoice("RE-2020-0001", 19.0, &"foo".to_string(), &project(), &[billable("dev", 2.5)], NaiveDate::from_ymd(2020, 10, 31), None);
+
+        let cancellation = InvoiceService::cancellation(&invoice, "RE-2020-0002", NaiveDate::from_ymd(2020, 11, 2));
+
+        assert_eq!(cancellation.cancels, Some("RE-2020-0001".to_string()));
+        assert_eq!(cancellation.line_items[0].quantity, -2.5);
+        assert_eq!((cancellation.net, cancellation.tax, cancellation.gross), (-20000, -3800, -23800));
+        assert_eq!(cancellation.entries, Vec::<u64>::new());
+        assert_eq!(cancellation.service_period, invoice.service_period);
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashSet;
+use std::thread;
+use std::time::Duration;
+use chrono::{Local, NaiveDate, TimeZone};
+use serde_json::{json, Value};
+use snafu::{OptionExt, ResultExt};
+
+use crate::constants::{CURRENCY, LEX_OFFICE_API_URL, LEX_OFFICE_BACKOFF_MILLIS, LEX_OFFICE_RETRIES, LEX_OFFICE_TIMEOUT_SECS};
+use crate::domain::errors::lex_office::{LexOfficeError, MissingApiKey, Request, UnexpectedResponse};
+use crate::domain::objects::Config;
+use crate::domain::objects::invoice::{Invoice, LineItem, ServicePeriod};
+use crate::domain::objects::project_data::BillableUnit;
+
+
+const INVOICES_PATH: &str = "/invoices";
+const CREDIT_NOTES_PATH: &str = "/credit-notes";
+const INVOICE_QUERY: &[(&str, &str)] = &[("finalize", "true")];
+/// Largest page of the voucher list LexOffice returns
+const VOUCHER_PAGE_SIZE: &str = "250";
+/// Longest wait LexOffice can ask for with `Retry-After` that is honoured
+const MAX_RETRY_AFTER_SECS: u64 = 60;
+
+/// Voucher LexOffice created for an invoice.
+#[derive(Debug, PartialEq)]
+pub struct Voucher {
+    pub id: String,
+    /// Number LexOffice assigned to the voucher
+    pub number: Option<String>
+}
+
+/// Client of the LexOffice public API.
+pub struct LexOfficeService {
+    api_key: String,
+    agent: ureq::Agent
+}
+
+impl LexOfficeService {
+    pub fn new(config: &Config) -> Result<LexOfficeService, LexOfficeError> {
+        let api_key = config.lex_office_api_key.clone().context(MissingApiKey)?;
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(LEX_OFFICE_TIMEOUT_SECS))
+            .build();
+
+        return Ok(LexOfficeService {
+            api_key,
+            agent
+        })
+    }
+
+    /// Creates `invoice` as a finalized invoice.
+    pub fn create_invoice(&self, invoice: &Invoice) -> Result<Voucher, LexOfficeError> {
+        let id = self.post(INVOICES_PATH, INVOICE_QUERY, &LexOfficeService::invoice(invoice))?;
+
+        return self.get_voucher(INVOICES_PATH, &id);
+    }
+
+    /// Finds `invoice` among the LexOffice invoices of its date by its customer and total in cents, skipping the
+    /// vouchers in `known` that other invoices were created as. For when the connection dropped before LexOffice
+    /// answered the request that created it.
+    pub fn find_invoice(&self, invoice: &Invoice, known: &HashSet<String>) -> Result<Option<Voucher>, LexOfficeError> {
+        return self.find_voucher("invoice", invoice, known);
+    }
+
+    /// Finds the credit note of `cancellation` like `find_invoice` finds invoices.
+    pub fn find_credit_note(&self, cancellation: &Invoice, known: &HashSet<String>) -> Result<Option<Voucher>, LexOfficeError> {
+        return self.find_voucher("creditnote", cancellation, known);
+    }
+
+    /// Pages through the vouchers of `voucher_type` dated like `invoice`. Credit notes list positive totals.
+    fn find_voucher(&self, voucher_type: &str, invoice: &Invoice, known: &HashSet<String>) -> Result<Option<Voucher>, LexOfficeError> {
+        let path = "/voucherlist";
+        let customer = invoice.customer.first().cloned().unwrap_or_default();
+        let mut page = 0;
+        loop {
+            let page_number = page.to_string();
+            let query = [
+                ("voucherType", voucher_type),
+                ("voucherStatus", "draft,open,paid,paidoff,voided"),
+                ("voucherDateFrom", invoice.date.as_str()),
+                ("voucherDateTo", invoice.date.as_str()),
+                ("size", VOUCHER_PAGE_SIZE),
+                ("page", page_number.as_str())
+            ];
+            let response = self.get(path, &query)?;
+            let vouchers = response.get("content").and_then(Value::as_array).context(UnexpectedResponse { path, message: "the response has no content" })?;
+
+            let found = vouchers.iter()
+                .filter(|voucher| voucher.get("contactName").and_then(Value::as_str) == Some(customer.as_str()))
+                .filter(|voucher| voucher.get("totalAmount").and_then(Value::as_f64).is_some_and(|amount| (amount * 100.0).round() as i64 == invoice.gross.abs()))
+                .filter_map(|voucher| voucher.get("id").and_then(Value::as_str).map(|id| (id, voucher)))
+                .find(|(id, _)| !known.contains(*id));
+            if let Some((id, voucher)) = found {
+                return Ok(Some(LexOfficeService::voucher(id, voucher)))
+            }
+            if vouchers.is_empty() || response.get("last").and_then(Value::as_bool).unwrap_or(true) {
+                return Ok(None)
+            }
+            page += 1;
+        }
+    }
+
+    fn get_voucher(&self, path: &str, id: &str) -> Result<Voucher, LexOfficeError> {
+        let voucher = self.get(&format!("{}/{}", path, id), &[])?;
+
+        return Ok(LexOfficeService::voucher(id, &voucher));
+    }
+
+    fn voucher(id: &str, invoice: &Value) -> Voucher {
+        return Voucher {
+            id: id.to_string(),
+            number: invoice.get("voucherNumber").and_then(Value::as_str).map(String::from)
+        }
+    }
+
+    /// Creates a finalized credit note for `cancellation`, linked to the LexOffice invoice `invoice_id`.
+    pub fn create_credit_note(&self, invoice_id: &str, cancellation: &Invoice) -> Result<Voucher, LexOfficeError> {
+        let query = [("finalize", "true"), ("precedingSalesVoucherId", invoice_id)];
+        let id = self.post(CREDIT_NOTES_PATH, &query, &LexOfficeService::credit_note(cancellation))?;
+
+        return self.get_voucher(CREDIT_NOTES_PATH, &id);
+    }
+
+    /// URL and body of the request that creates `invoice`, invoices are finalized right away.
+    pub fn invoice_request(invoice: &Invoice) -> (String, Value) {
+        let query: Vec<String> = INVOICE_QUERY.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+
+        return (format!("{}{}?{}", LEX_OFFICE_API_URL, INVOICES_PATH, query.join("&")), LexOfficeService::invoice(invoice));
+    }
+
+    /// Body of the invoice request.
+    pub fn invoice(invoice: &Invoice) -> Value {
+        return voucher(invoice);
+    }
+
+    /// Body of the credit note request. Credit notes hold positive amounts, LexOffice subtracts them.
+    pub fn credit_note(cancellation: &Invoice) -> Value {
+        let mut body = voucher(cancellation);
+        if let Some(cancelled) = &cancellation.cancels {
+            body["introduction"] = json!(format!("Cancels invoice {}", cancelled));
+        }
+
+        return body;
+    }
+
+    /// Posts `body` and returns the id of the created resource.
+    /// Only rate limited requests are retried, LexOffice may have created the resource when it fails otherwise.
+    fn post(&self, path: &str, query: &[(&str, &str)], body: &Value) -> Result<String, LexOfficeError> {
+        let response = self.send(path, self.request("POST", path, query), Some(body), |status| status == 429)?;
+
+        return response.get("id")
+            .and_then(Value::as_str)
+            .map(String::from)
+            .context(UnexpectedResponse { path, message: "the response has no id" });
+    }
+
+    /// Retries rate limited requests and server errors.
+    fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<Value, LexOfficeError> {
+        return self.send(path, self.request("GET", path, query), None, |status| status == 429 || status >= 500);
+    }
+
+    fn request(&self, method: &str, path: &str, query: &[(&str, &str)]) -> ureq::Request {
+        let mut request = self.agent.request(method, &format!("{}{}", LEX_OFFICE_API_URL, path))
+            .set("Authorization", &format!("Bearer {}", self.api_key))
+            .set("Accept", "application/json");
+        for (key, value) in query.iter() {
+            request = request.query(key, value);
+        }
+
+        return request;
+    }
+
+    /// Sends `request`, waiting and trying again up to `LEX_OFFICE_RETRIES` times while `retry` holds for the status.
+    fn send(&self, path: &str, request: ureq::Request, body: Option<&Value>, retry: fn(u16) -> bool) -> Result<Value, LexOfficeError> {
+        let mut attempt = 0;
+        loop {
+            let result = match body {
+                Some(body) => request.clone().send_json(body.clone()),
+                None => request.clone().call()
+            };
+            match result {
+                Ok(response) => {
+                    return response.into_json().map_err(|e| UnexpectedResponse { path, message: e.to_string() }.build())
+                },
+                Err(ureq::Error::Status(status, response)) if retry(status) && attempt < LEX_OFFICE_RETRIES => {
+                    thread::sleep(backoff(attempt, response.header("Retry-After")));
+                    attempt += 1;
+                },
+                Err(e) => return Err(Box::new(e)).context(Request)
+            }
+        }
+    }
+}
+
+/// Wait before retry `attempt`: as long as LexOffice asks for, else doubling from `LEX_OFFICE_BACKOFF_MILLIS`.
+fn backoff(attempt: u32, retry_after: Option<&str>) -> Duration {
+    return match retry_after.and_then(|seconds| seconds.trim().parse::<u64>().ok()) {
+        Some(seconds) => Duration::from_secs(seconds.min(MAX_RETRY_AFTER_SECS)),
+        None => Duration::from_millis(LEX_OFFICE_BACKOFF_MILLIS << attempt)
+    }
+}
+
+/// Fields invoices and credit notes have in common.
+fn voucher(invoice: &Invoice) -> Value {
+    let mut body = json!({
+        "voucherDate": voucher_date(&invoice.date),
+        "address": address(&invoice.customer),
+        "lineItems": invoice.line_items.iter().map(|item| line_item(item, invoice.tax_rate)).collect::<Vec<Value>>(),
+        "totalPrice": { "currency": CURRENCY },
+        "taxConditions": { "taxType": "net" }
+    });
+    if let Some(period) = &invoice.service_period {
+        body["shippingConditions"] = shipping_conditions(period);
+    }
+
+    return body;
+}
+
+/// The service period, a single day is a service date.
+fn shipping_conditions(period: &ServicePeriod) -> Value {
+    if period.start == period.end {
+        return json!({ "shippingType": "service", "shippingDate": voucher_date(&period.start) })
+    }
+
+    return json!({
+        "shippingType": "serviceperiod",
+        "shippingDate": voucher_date(&period.start),
+        "shippingEndDate": voucher_date(&period.end)
+    });
+}
+
+/// Midnight of `date` in local time, LexOffice rejects dates without time and offset.
+fn voucher_date(date: &str) -> String {
+    let midnight = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+        .and_then(|date| Local.from_local_date(&date).earliest())
+        .map(|date| date.and_hms(0, 0, 0))
+        .unwrap_or_else(Local::now);
+
+    return midnight.format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string();
+}
+
+/// Address lines as name, street and `zip city`, further lines in between become the supplement.
+fn address(lines: &[String]) -> Value {
+    let mut address = json!({ "name": lines.first().cloned().unwrap_or_default(), "countryCode": "DE" });
+    if lines.len() > 1 {
+        let last = &lines[lines.len() - 1];
+        match last.split_once(' ') {
+            Some((zip, city)) if zip.chars().all(|c| c.is_ascii_digit()) => {
+                address["zip"] = json!(zip);
+                address["city"] = json!(city);
+            },
+            _ => address["city"] = json!(last)
+        }
+    }
+    if lines.len() > 2 {
+        address["street"] = json!(lines[lines.len() - 2]);
+    }
+    if lines.len() > 3 {
+        address["supplement"] = json!(lines[1..lines.len() - 2].join(", "));
+    }
+
+    return address;
+}
+
+fn line_item(item: &LineItem, tax_rate: f32) -> Value {
+    let unit_name = match item.unit {
+        BillableUnit::Hour => "Stunde",
+        BillableUnit::Day => "Tag"
+    };
+
+    return json!({
+        "type": "custom",
+        "name": item.description,
+        "quantity": item.quantity.abs(),
+        "unitName": unit_name,
+        "unitPrice": { "currency": CURRENCY, "netAmount": item.unit_price, "taxRatePercentage": tax_rate }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::objects::invoice::InvoiceStatus;
+
+    fn cancellation() -> Invoice {
+        return Invoice {
+            number: "RE-2020-0002".to_string(),
+            cancels: Some("RE-2020-0001".to_string()),
+            project_id: "foo".to_string(),
+            customer: vec!["Foo GmbH".to_string(), "c/o Bar".to_string(), "Hauptstraße 1".to_string(), "10115 Berlin".to_string()],
+            date: "2020-11-02".to_string(),
+            service_period: None,
+            line_items: vec![LineItem { description: "dev".to_string(), quantity: -2.5, unit: BillableUnit::Hour, unit_price: 80 }],
+            net: -20000,
+            tax_rate: 19.0,
+            tax: -3800,
+            gross: -23800,
+            status: InvoiceStatus::Draft,
+            lex_office_id: None,
+            lex_office_number: None,
+            submission: None,
+            entries: vec![]
+        }
+    }
+
+    #[test]
+    fn test_credit_note() {
+        let body = LexOfficeService::credit_note(&cancellation());
+
+        assert_eq!(body["address"], json!({
+            "name": "Foo GmbH", "supplement": "c/o Bar", "street": "Hauptstraße 1", "zip": "10115", "city": "Berlin", "countryCode": "DE"
+        }));
+        assert_eq!(body["lineItems"][0]["quantity"], json!(2.5));
+        assert_eq!(body["lineItems"][0]["unitPrice"]["netAmount"], json!(80));
+        assert_eq!(body["voucherDate"].as_str().unwrap().starts_with("2020-11-02T00:00:00.000"), true);
+        assert_eq!(body["introduction"], json!("Cancels invoice RE-2020-0001"));
+    }
+
+    #[test]
+    fn test_invoice_states_service_period() {
+        let period = ServicePeriod { start: "2020-10-01".to_string(), end: "2020-10-31".to_string() };
+        let invoice = Invoice { cancels: None, service_period: Some(period.clone()), ..cancellation() };
+
+        let body = LexOfficeService::invoice(&invoice);
+        let single_day = LexOfficeService::invoice(&Invoice { service_period: Some(ServicePeriod { end: period.start.clone(), ..period }), ..invoice });
+
+        assert_eq!(body["shippingConditions"]["shippingType"], json!("serviceperiod"));
+        assert_eq!(body["shippingConditions"]["shippingDate"].as_str().unwrap().starts_with("2020-10-01T00:00:00.000"), true);
+        assert_eq!(body["shippingConditions"]["shippingEndDate"].as_str().unwrap().starts_with("2020-10-31T00:00:00.000"), true);
+        assert_eq!(body.get("introduction"), None);
+        assert_eq!(single_day["shippingConditions"]["shippingType"], json!("service"));
+        assert_eq!(single_day["shippingConditions"].get("shippingEndDate"), None);
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(0, None), Duration::from_millis(LEX_OFFICE_BACKOFF_MILLIS));
+        assert_eq!(backoff(2, None), Duration::from_millis(LEX_OFFICE_BACKOFF_MILLIS * 4));
+        assert_eq!(backoff(0, Some("3")), Duration::from_secs(3));
+        assert_eq!(backoff(0, Some("3600")), Duration::from_secs(MAX_RETRY_AFTER_SECS));
+        assert_eq!(backoff(1, Some("Wed, 21 Oct 2020 07:28:00 GMT")), Duration::from_millis(LEX_OFFICE_BACKOFF_MILLIS * 2));
+    }
+
+    #[test]
+    fn test_invoice_request() {
+        let invoice = Invoice { cancels: None, ..cancellation() };
+
+        let (url, body) = LexOfficeService::invoice_request(&invoice);
+
+        assert_eq!(url, format!("{}/invoices?finalize=true", LEX_OFFICE_API_URL));
+        assert_eq!(body, LexOfficeService::invoice(&invoice));
+    }
+}
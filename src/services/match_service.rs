@@ -0,0 +1,90 @@
+use strsim::normalized_damerau_levenshtein;
+
+use crate::domain::objects::suggestions::Suggestions;
+
+const SIMILARITY_THRESHOLD: f64 = 0.7;
+const MAX_SUGGESTIONS: usize = 3;
+
+#[derive(Debug, PartialEq)]
+pub enum Match {
+    Found(String),
+    Ambiguous(Vec<String>),
+    NotFound { suggestions: Suggestions }
+}
+
+pub struct MatchService;
+
+impl MatchService {
+    /// Finds the value `input` refers to. `candidates` pairs each slug a value can be referred to by with the value.
+    /// An exact slug wins over prefixes, a prefix has to match a single value. Otherwise similar values are suggested.
+    pub fn find(input: &str, candidates: &[(String, String)]) -> Match {
+        for matches_input in [|key: &String, input: &str| key == input, |key: &String, input: &str| key.starts_with(input)].iter() {
+            let mut values: Vec<String> = candidates.iter()
+                .filter(|(key, _)| matches_input(key, input))
+                .map(|(_, value)| value.to_string())
+                .collect();
+            values.sort();
+            values.dedup();
+
+            match values.len() {
+                0 => continue,
+                1 => return Match::Found(values.remove(0)),
+                _ => return Match::Ambiguous(values)
+            }
+        }
+
+        return Match::NotFound { suggestions: Suggestions(MatchService::suggestions(input, candidates)) };
+    }
+
+    fn suggestions(input: &str, candidates: &[(String, String)]) -> Vec<String> {
+        let mut scored: Vec<(f64, &String)> = candidates.iter()
+            .map(|(key, value)| (normalized_damerau_levenshtein(input, key), value))
+            .filter(|(score, _)| *score >= SIMILARITY_THRESHOLD)
+            .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut suggestions: Vec<String> = vec![];
+        for (_, value) in scored {
+            if !suggestions.contains(value) && suggestions.len() < MAX_SUGGESTIONS {
+                suggestions.push(value.to_string());
+            }
+        }
+
+        return suggestions;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn candidates() -> Vec<(String, String)> {
+        return vec![
+            ("development".to_string(), "Development".to_string()),
+            ("devops".to_string(), "DevOps".to_string()),
+            ("design".to_string(), "Design".to_string()),
+            ("d".to_string(), "Design".to_string())
+        ]
+    }
+
+    #[test]
+    fn test_find_exact_before_prefix() {
+        assert_eq!(MatchService::find("d", &candidates()), Match::Found("Design".to_string()));
+    }
+
+    #[test]
+    fn test_find_unique_prefix() {
+        assert_eq!(MatchService::find("develop", &candidates()), Match::Found("Development".to_string()));
+    }
+
+    #[test]
+    fn test_find_ambiguous_prefix() {
+        assert_eq!(MatchService::find("dev", &candidates()), Match::Ambiguous(vec!["DevOps".to_string(), "Development".to_string()]));
+    }
+
+    #[test]
+    fn test_find_suggests_similar() {
+        assert_eq!(MatchService::find("devlopment", &candidates()), Match::NotFound { suggestions: Suggestions(vec!["Development".to_string()]) });
+        assert_eq!(MatchService::find("xyz", &candidates()), Match::NotFound { suggestions: Suggestions::default() });
+    }
+}
@@ -0,0 +1,133 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::domain::errors::project_data::ProjectDataError;
+use crate::domain::objects::invoice::Invoice;
+use crate::domain::objects::project_data::{BillableEntry, InvoiceSequence, Project, ProjectData};
+use crate::services::project_store::ProjectStore;
+
+
+/// Store that keeps everything in memory, `save` is a no-op.
+pub struct MemoryStore {
+    data: RefCell<ProjectData>
+}
+
+impl MemoryStore {
+    pub fn new(data: ProjectData) -> MemoryStore {
+        MemoryStore {
+            data: RefCell::new(data)
+        }
+    }
+}
+
+impl ProjectStore for MemoryStore {
+    fn get_project(&self, project_id: &String) -> Result<Option<Project>, ProjectDataError> {
+        return Ok(self.data.borrow().projects.get(project_id).cloned());
+    }
+
+    fn get_projects(&self) -> Result<HashMap<String, Project>, ProjectDataError> {
+        return Ok(self.data.borrow().projects.clone());
+    }
+
+    fn insert_project(&self, project_id: &String, project: Project) -> Result<(), ProjectDataError> {
+        self.data.borrow_mut().projects.insert(project_id.to_string(), project);
+
+        return Ok(());
+    }
+
+    fn insert_task(&self, project_id: &String, task_name: &String) -> Result<bool, ProjectDataError> {
+        if let Some(p) = self.data.borrow_mut().projects.get_mut(project_id) {
+            p.tasks.insert(task_name.to_string());
+            return Ok(true)
+        }
+
+        return Ok(false)
+    }
+
+    fn get_entries(&self, project_id: &String) -> Result<Vec<BillableEntry>, ProjectDataError> {
+        return Ok(self.data.borrow().billable.iter()
+            .filter(|e| &e.project_id == project_id)
+            .cloned()
+            .collect());
+    }
+
+    fn insert_entry(&self, entry: BillableEntry) -> Result<(), ProjectDataError> {
+        self.data.borrow_mut().push_entry(entry);
+
+        return Ok(());
+    }
+
+    fn next_entry_id(&self) -> Result<u64, ProjectDataError> {
+        return Ok(self.data.borrow().next_entry_id());
+    }
+
+    fn remove_entry(&self, entry: &BillableEntry) -> Result<bool, ProjectDataError> {
+        let mut data = self.data.borrow_mut();
+
+        return match data.billable.iter().rposition(|e| e == entry) {
+            Some(index) => {
+                data.billable.remove(index);
+                Ok(true)
+            },
+            None => Ok(false)
+        }
+    }
+
+    fn get_selected_project(&self) -> Result<Option<String>, ProjectDataError> {
+        return Ok(self.data.borrow().selected_project.clone());
+    }
+
+    fn set_selected_project(&self, project_id: &String) -> Result<(), ProjectDataError> {
+        self.data.borrow_mut().selected_project = Some(project_id.to_string());
+
+        return Ok(());
+    }
+
+    fn clear_selected_project(&self) -> Result<(), ProjectDataError> {
+        self.data.borrow_mut().selected_project = None;
+
+        return Ok(());
+    }
+
+    fn get_invoice_sequence(&self) -> Result<InvoiceSequence, ProjectDataError> {
+        return Ok(self.data.borrow().invoice_numbers.clone());
+    }
+
+    fn set_invoice_sequence(&self, sequence: InvoiceSequence) -> Result<(), ProjectDataError> {
+        self.data.borrow_mut().invoice_numbers = sequence;
+
+        return Ok(());
+    }
+
+    fn get_invoices(&self) -> Result<Vec<Invoice>, ProjectDataError> {
+        return Ok(self.data.borrow().invoices.clone());
+    }
+
+    fn insert_invoice(&self, invoice: Invoice) -> Result<(), ProjectDataError> {
+        let mut data = self.data.borrow_mut();
+        match data.invoices.iter_mut().find(|i| i.number == invoice.number) {
+            Some(existing) => *existing = invoice,
+            None => data.invoices.push(invoice)
+        }
+
+        return Ok(());
+    }
+
+    fn snapshot(&self) -> Result<ProjectData, ProjectDataError> {
+        return Ok(self.data.borrow().clone());
+    }
+
+    fn replace(&self, data: ProjectData) -> Result<(), ProjectDataError> {
+        self.data.replace(data);
+
+        return Ok(());
+    }
+
+    fn backup(&self, _target: &String) -> Result<(), ProjectDataError> {
+        return Ok(());
+    }
+
+    fn save(&self) -> Result<(), ProjectDataError> {
+        return Ok(());
+    }
+}
@@ -2,4 +2,21 @@ pub mod file_service;
 pub mod toml_service;
 pub mod config_file_service;
 pub mod file_path_service;
+pub mod slug_service;
+pub mod match_service;
+pub mod date_service;
+pub mod invoice_service;
+pub mod invoice_number_service;
+pub mod pdf_service;
+pub mod lex_office_service;
+pub mod template_service;
 pub mod file_database_service;
+pub mod audit_log_service;
+pub mod backup_service;
+pub mod integrity_service;
+pub mod file_lock;
+pub mod project_store;
+pub mod schema_migrations;
+pub mod rustbreak_store;
+pub mod sqlite_store;
+pub mod memory_store;
@@ -0,0 +1,279 @@
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+use crate::domain::objects::invoice::{format_amount, InvoiceDraft};
+use crate::domain::objects::project_data::{BillableUnit, Quantity};
+
+// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+const RIGHT: f32 = PAGE_WIDTH - MARGIN;
+const FONT_SIZE: f32 = 10.0;
+const LINE_HEIGHT: f32 = 14.0;
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+// Right edges of the numeric table columns
+const QUANTITY_COLUMN: f32 = 340.0;
+const PRICE_COLUMN: f32 = 440.0;
+
+
+pub struct PdfService;
+
+impl PdfService {
+    /// Renders the invoice as an A4 PDF. Only the standard Helvetica fonts are used, so nothing has to be embedded.
+    pub fn render(invoice: &InvoiceDraft) -> Vec<u8> {
+        let mut layout = Layout::new();
+
+        let sender = &invoice.sender;
+        layout.text(BOLD, FONT_SIZE, 360.0, &sender.name);
+        for line in sender.address.iter().chain(sender.tax_id.iter()) {
+            layout.next_line();
+            layout.text(REGULAR, FONT_SIZE, 360.0, line);
+        }
+
+        layout.y = 690.0;
+        let return_address: Vec<&str> = std::iter::once(&sender.name).chain(sender.address.iter()).map(String::as_str).collect();
+        layout.text(REGULAR, 7.0, MARGIN, &return_address.join(" · "));
+        for line in invoice.customer.iter() {
+            layout.next_line();
+            layout.text(REGULAR, FONT_SIZE, MARGIN, line);
+        }
+
+        layout.y = 560.0;
+        let title = match invoice.cancels {
+            Some(_) => format!("Cancellation {}", invoice.number),
+            None => format!("Invoice {}", invoice.number)
+        };
+        layout.text(BOLD, 16.0, MARGIN, &title);
+        layout.text_right(REGULAR, FONT_SIZE, RIGHT, &format!("Date: {}", invoice.date.format("%Y-%m-%d")));
+        if let Some(period) = &invoice.service_period {
+            layout.next_line();
+            layout.text_right(REGULAR, FONT_SIZE, RIGHT, &format!("Service period: {} to {}", period.start, period.end));
+        }
+        if let Some(cancelled) = &invoice.cancels {
+            layout.next_line();
+            layout.text(REGULAR, FONT_SIZE, MARGIN, &format!("Cancels invoice {}", cancelled));
+        }
+        layout.skip(2.0);
+
+        layout.text(BOLD, FONT_SIZE, MARGIN, "Description");
+        layout.text_right(BOLD, FONT_SIZE, QUANTITY_COLUMN, "Quantity");
+        layout.text_right(BOLD, FONT_SIZE, PRICE_COLUMN, "Unit price");
+        layout.text_right(BOLD, FONT_SIZE, RIGHT, "Amount");
+        layout.rule();
+        for item in invoice.line_items.iter() {
+            layout.next_line();
+            layout.text(REGULAR, FONT_SIZE, MARGIN, &item.description);
+            layout.text_right(REGULAR, FONT_SIZE, QUANTITY_COLUMN, &format_quantity(item.quantity, &item.unit));
+            layout.text_right(REGULAR, FONT_SIZE, PRICE_COLUMN, &format_amount(item.unit_price as i64 * 100));
+            layout.text_right(REGULAR, FONT_SIZE, RIGHT, &format_amount(item.net()));
+        }
+        layout.rule();
+
+        layout.next_line();
+        layout.text_right(REGULAR, FONT_SIZE, PRICE_COLUMN, "Net");
+        layout.text_right(REGULAR, FONT_SIZE, RIGHT, &format_amount(invoice.net()));
+        layout.next_line();
+        layout.text_right(REGULAR, FONT_SIZE, PRICE_COLUMN, &format!("VAT {}%", invoice.tax_rate));
+        layout.text_right(REGULAR, FONT_SIZE, RIGHT, &format_amount(invoice.tax()));
+        layout.next_line();
+        layout.text_right(BOLD, FONT_SIZE, PRICE_COLUMN, "Total");
+        layout.text_right(BOLD, FONT_SIZE, RIGHT, &format_amount(invoice.gross()));
+
+        // Cancellations are not paid
+        if invoice.cancels.is_none() {
+            let bank = &invoice.bank;
+            layout.skip(3.0);
+            layout.text(REGULAR, FONT_SIZE, MARGIN, "Please transfer the total to:");
+            let account = vec![
+                Some(bank.account_holder.to_string()),
+                Some(format!("IBAN: {}", bank.iban)),
+                bank.bic.as_ref().map(|bic| format!("BIC: {}", bic)),
+                bank.bank_name.clone()
+            ];
+            for line in account.into_iter().flatten() {
+                layout.next_line();
+                layout.text(REGULAR, FONT_SIZE, MARGIN, &line);
+            }
+        }
+
+        return layout.finish();
+    }
+}
+
+/// Text placed top to bottom, continuing on a new page when the current one is full.
+struct Layout {
+    pages: Vec<Content>,
+    y: f32
+}
+
+impl Layout {
+    fn new() -> Layout {
+        return Layout {
+            pages: vec![Content::new()],
+            y: PAGE_HEIGHT - MARGIN
+        }
+    }
+
+    fn text(&mut self, font: Name, size: f32, x: f32, text: &str) {
+        let y = self.y;
+        self.page()
+            .begin_text()
+            .set_font(font, size)
+            .next_line(x, y)
+            .show(Str(&win_ansi(text)))
+            .end_text();
+    }
+
+    fn text_right(&mut self, font: Name, size: f32, right: f32, text: &str) {
+        self.text(font, size, right - text_width(text, size), text);
+    }
+
+    fn rule(&mut self) {
+        let y = self.y - 4.0;
+        self.page().set_line_width(0.5).move_to(MARGIN, y).line_to(RIGHT, y).stroke();
+        self.y -= 4.0;
+    }
+
+    fn next_line(&mut self) {
+        self.skip(1.0);
+    }
+
+    fn skip(&mut self, lines: f32) {
+        self.y -= lines * LINE_HEIGHT;
+        if self.y < MARGIN {
+            self.pages.push(Content::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn page(&mut self) -> &mut Content {
+        return self.pages.last_mut().expect("layout always has a page");
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let regular_id = Ref::new(3);
+        let bold_id = Ref::new(4);
+        // Every page is followed by its content stream
+        let page_ids: Vec<Ref> = (0..self.pages.len() as i32).map(|i| Ref::new(5 + 2 * i)).collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.pages(page_tree_id).kids(page_ids.iter().copied()).count(page_ids.len() as i32);
+        pdf.type1_font(regular_id).base_font(Name(b"Helvetica")).encoding_predefined(Name(b"WinAnsiEncoding"));
+        pdf.type1_font(bold_id).base_font(Name(b"Helvetica-Bold")).encoding_predefined(Name(b"WinAnsiEncoding"));
+
+        for (page_id, content) in page_ids.into_iter().zip(self.pages) {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(page_id);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+            page.parent(page_tree_id);
+            page.contents(content_id);
+            page.resources().fonts().pair(REGULAR, regular_id).pair(BOLD, bold_id);
+            page.finish();
+            pdf.stream(content_id, &content.finish());
+        }
+
+        return pdf.finish();
+    }
+}
+
+fn format_quantity(quantity: Quantity, unit: &BillableUnit) -> String {
+    let unit = match unit {
+        BillableUnit::Hour => "h",
+        BillableUnit::Day => "d"
+    };
+
+    return format!("{} {}", quantity, unit);
+}
+
+/// Encodes text for the standard fonts, characters WinAnsi does not have become `?`.
+fn win_ansi(text: &str) -> Vec<u8> {
+    return text.chars().map(|c| match c {
+        '\u{20}'..='\u{7e}' | '\u{a0}'..='\u{ff}' => c as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        '„' => 0x84,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        _ => b'?'
+    }).collect();
+}
+
+/// Width of `text` in Helvetica, exact for digits and punctuation and close enough for letters.
+fn text_width(text: &str, size: f32) -> f32 {
+    let units: u32 = text.chars().map(|c| match c {
+        ' ' | '.' | ',' | ':' | 'i' | 'l' | 'I' | 'j' | 't' | 'f' => 278,
+        '-' | 'r' | '(' | ')' => 333,
+        '%' => 889,
+        'm' | 'M' | 'W' => 833,
+        'w' => 722,
+        c if c.is_ascii_uppercase() => 667,
+        _ => 556
+    }).sum();
+
+    return units as f32 * size / 1000.0;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+    use crate::domain::objects::{BankDetails, Sender};
+    use crate::domain::objects::invoice::LineItem;
+
+    fn invoice(line_items: usize) -> InvoiceDraft {
+        return InvoiceDraft {
+            number: "RE-2020-0001".to_string(),
+            cancels: None,
+            sender: Sender { name: "Me".to_string(), address: vec!["Hauptstraße 1".to_string()], tax_id: None },
+            bank: BankDetails { account_holder: "Me".to_string(), iban: "DE00".to_string(), bic: None, bank_name: None },
+            customer: vec!["Foo GmbH".to_string()],
+            date: NaiveDate::from_ymd(2020, 10, 31),
+            service_period: None,
+            line_items: (0..line_items)
+                .map(|i| LineItem { description: format!("task {}", i), quantity: 1.0, unit: BillableUnit::Hour, unit_price: 80 })
+                .collect(),
+            tax_rate: 19.0
+        }
+    }
+
+    #[test]
+    fn test_render_pdf() {
+        let pdf = PdfService::render(&invoice(2));
+
+        assert_eq!(pdf.starts_with(b"%PDF-"), true);
+        assert_eq!(String::from_utf8_lossy(&pdf).contains("/Count 1"), true);
+    }
+
+    #[test]
+    fn test_render_long_invoice_on_several_pages() {
+        let pdf = PdfService::render(&invoice(80));
+
+        assert_eq!(String::from_utf8_lossy(&pdf).contains("/Count 3"), true);
+    }
+
+    #[test]
+    fn test_cancellation_asks_for_no_payment() {
+        let cancellation = InvoiceDraft { cancels: Some("RE-2020-0001".to_string()), ..invoice(2) };
+
+        assert_eq!(String::from_utf8_lossy(&PdfService::render(&invoice(2))).contains("Please transfer"), true);
+        assert_eq!(String::from_utf8_lossy(&PdfService::render(&cancellation)).contains("Please transfer"), false);
+    }
+
+    #[test]
+    fn test_win_ansi() {
+        assert_eq!(win_ansi("Straße 10 €"), vec![b'S', b't', b'r', b'a', 0xdf, b'e', b' ', b'1', b'0', b' ', 0x80]);
+        assert_eq!(win_ansi("日本"), b"??".to_vec());
+    }
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use crate::domain::errors::project_data::ProjectDataError;
+use crate::domain::objects::invoice::Invoice;
+use crate::domain::objects::project_data::{BillableEntry, InvoiceSequence, Project, ProjectData};
+
+
+/// Persistence of `ProjectData`. Implementations may buffer changes until `save` is called.
+pub trait ProjectStore {
+    fn get_project(&self, project_id: &String) -> Result<Option<Project>, ProjectDataError>;
+    fn get_projects(&self) -> Result<HashMap<String, Project>, ProjectDataError>;
+    fn insert_project(&self, project_id: &String, project: Project) -> Result<(), ProjectDataError>;
+    /// Adds a task to an existing project, returns false when the project does not exist.
+    fn insert_task(&self, project_id: &String, task_name: &String) -> Result<bool, ProjectDataError>;
+    fn get_entries(&self, project_id: &String) -> Result<Vec<BillableEntry>, ProjectDataError>;
+    fn insert_entry(&self, entry: BillableEntry) -> Result<(), ProjectDataError>;
+    /// Id for a new entry, higher than the ids of all entries stored so far, including deleted ones.
+    fn next_entry_id(&self) -> Result<u64, ProjectDataError>;
+    /// Removes the latest entry equal to `entry`, returns false when there is none.
+    fn remove_entry(&self, entry: &BillableEntry) -> Result<bool, ProjectDataError>;
+    fn get_selected_project(&self) -> Result<Option<String>, ProjectDataError>;
+    fn set_selected_project(&self, project_id: &String) -> Result<(), ProjectDataError>;
+    fn clear_selected_project(&self) -> Result<(), ProjectDataError>;
+    fn get_invoice_sequence(&self) -> Result<InvoiceSequence, ProjectDataError>;
+    fn set_invoice_sequence(&self, sequence: InvoiceSequence) -> Result<(), ProjectDataError>;
+    /// All invoices, oldest first.
+    fn get_invoices(&self) -> Result<Vec<Invoice>, ProjectDataError>;
+    /// Adds an invoice or replaces the one with the same number.
+    fn insert_invoice(&self, invoice: Invoice) -> Result<(), ProjectDataError>;
+    /// Returns a copy of all stored data.
+    fn snapshot(&self) -> Result<ProjectData, ProjectDataError>;
+    /// Replaces all stored data.
+    fn replace(&self, data: ProjectData) -> Result<(), ProjectDataError>;
+    /// Copies the last saved state to `target`, in the same format as the store itself.
+    fn backup(&self, target: &String) -> Result<(), ProjectDataError>;
+    fn save(&self) -> Result<(), ProjectDataError>;
+}
@@ -0,0 +1,512 @@
+use bincode::Options;
+use rustbreak::{DeSerError, PathDatabase};
+use rustbreak::deser::{Bincode, DeSerializer, Ron, Yaml};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use snafu::ResultExt;
+use tempfile::NamedTempFile;
+
+use crate::constants::DATABASE_LOCK_TIMEOUT_SECS;
+use crate::domain::errors::project_data::{ProjectDataError, CreateDataDirectory, Database, DecodeDataFile, Encode, ReadDataFile, UnknownEncoding, WriteDataFile};
+use crate::domain::objects::Encoding;
+use crate::domain::objects::invoice::Invoice;
+use crate::domain::objects::legacy::{ProjectDataV1, VersionedProjectDataV1, VersionedProjectDataV2, VersionedProjectDataV3, VersionedProjectDataV4, VersionedProjectDataV5, VersionedProjectDataV6, VersionedProjectDataV7, VersionedProjectDataV9};
+use crate::domain::objects::project_data::{BillableEntry, InvoiceSequence, Project, ProjectData, VersionedProjectData};
+use crate::services::file_lock::FileLock;
+use crate::services::project_store::ProjectStore;
+use crate::services::schema_migrations;
+
+/// Saves go to a temporary file that is renamed over the database, so a crash never leaves a half written file.
+type DB<D> = PathDatabase<VersionedProjectData, D>;
+
+const DETECTION_ORDER: [Encoding; 3] = [Encoding::Yaml, Encoding::Ron, Encoding::Bin];
+
+
+/// Ties rustbreak's serializers to the `Encoding` they write.
+pub trait Encoded {
+    const ENCODING: Encoding;
+}
+
+impl Encoded for Yaml {
+    const ENCODING: Encoding = Encoding::Yaml;
+}
+
+impl Encoded for Bincode {
+    const ENCODING: Encoding = Encoding::Bin;
+}
+
+impl Encoded for Ron {
+    const ENCODING: Encoding = Encoding::Ron;
+}
+
+/// The database file is locked for the lifetime of the store, from load until the last save.
+pub struct RustbreakStore<D: DeSerializer<VersionedProjectData>> {
+    db: DB<D>,
+    path: String,
+    _lock: FileLock
+}
+
+impl<D: DeSerializer<VersionedProjectData> + Encoded> RustbreakStore<D> {
+    /// Loads the file at `path` and migrates it to the current schema, a file that can not be read is an error.
+    pub fn new(path: String) -> Result<RustbreakStore<D>, ProjectDataError> {
+        if let Some(parent) = Path::new(&path).parent() {
+            fs::create_dir_all(parent).context(CreateDataDirectory { path: parent.to_string_lossy() })?;
+        }
+
+        let lock = FileLock::acquire(&path, Duration::from_secs(DATABASE_LOCK_TIMEOUT_SECS))?;
+        let data = match read_content(&path)? {
+            Some(content) => decode(&path, &D::ENCODING, &content)?,
+            None => ProjectData::default()
+        };
+        let db: DB<D> = PathDatabase::create_at_path(PathBuf::from(&path), schema_migrations::current(data)).context(Database)?;
+
+        return Ok(RustbreakStore {
+            db,
+            path,
+            _lock: lock
+        })
+    }
+}
+
+/// Opens the database at `path` in the encoding it was written with. Empty or missing files use `encoding`.
+pub fn open(path: String, encoding: Encoding) -> Result<Box<dyn ProjectStore>, ProjectDataError> {
+    let encoding = detect_encoding(&path)?.unwrap_or(encoding);
+
+    return open_as(path, encoding);
+}
+
+/// Opens the database at `path` assuming it is written in `encoding`.
+pub fn open_as(path: String, encoding: Encoding) -> Result<Box<dyn ProjectStore>, ProjectDataError> {
+    return match encoding {
+        Encoding::Yaml => Ok(Box::new(RustbreakStore::<Yaml>::new(path)?)),
+        Encoding::Bin => Ok(Box::new(RustbreakStore::<Bincode>::new(path)?)),
+        Encoding::Ron => Ok(Box::new(RustbreakStore::<Ron>::new(path)?))
+    }
+}
+
+/// Finds the encoding the file at `path` can be read with, `None` when there is nothing to read.
+pub fn detect_encoding(path: &String) -> Result<Option<Encoding>, ProjectDataError> {
+    let content = match read_content(path)? {
+        Some(content) => content,
+        None => return Ok(None)
+    };
+
+    for encoding in DETECTION_ORDER.iter() {
+        if decode_value(encoding, &content).map(|v| v.is_object()).unwrap_or(false) {
+            return Ok(Some(encoding.clone()))
+        }
+    }
+
+    return UnknownEncoding { path }.fail()
+}
+
+/// Schema version of the file at `path`, `None` when there is nothing to read.
+/// Fails when the file can not be migrated to the current version.
+pub fn check_schema(path: &String) -> Result<Option<u64>, ProjectDataError> {
+    let encoding = match detect_encoding(path)? {
+        Some(encoding) => encoding,
+        None => return Ok(None)
+    };
+    let content = fs::read(path).context(ReadDataFile { path })?;
+    let value = decode_value(&encoding, &content).context(DecodeDataFile { path })?;
+    let version = schema_migrations::version_of(&value);
+
+    schema_migrations::migrate(path, value)?;
+
+    return Ok(Some(version));
+}
+
+/// Serializes `data` in `encoding` and atomically replaces the file at `path` with it.
+/// The caller is expected to hold the lock of `path`.
+pub fn write_encoded(path: &String, encoding: &Encoding, data: &ProjectData) -> Result<(), ProjectDataError> {
+    let versioned = schema_migrations::current(data.clone());
+    let content = match encoding {
+        Encoding::Yaml => Yaml.serialize(&versioned),
+        Encoding::Bin => Bincode.serialize(&versioned),
+        Encoding::Ron => Ron.serialize(&versioned)
+    }.context(Encode)?;
+    let directory = Path::new(path).parent().unwrap_or_else(|| Path::new("."));
+
+    let mut file = NamedTempFile::new_in(directory).context(WriteDataFile { path })?;
+    file.write_all(&content).context(WriteDataFile { path })?;
+    file.as_file().sync_all().context(WriteDataFile { path })?;
+    file.persist(path).map_err(|e| e.error).context(WriteDataFile { path })?;
+
+    return Ok(());
+}
+
+/// Reads the file at `path` in `encoding` and migrates it to the current schema.
+pub fn read_encoded(path: &String, encoding: &Encoding) -> Result<ProjectData, ProjectDataError> {
+    let content = fs::read(path).context(ReadDataFile { path })?;
+
+    return decode(path, encoding, &content);
+}
+
+/// Content of the file at `path`, `None` when it is missing or empty.
+fn read_content(path: &String) -> Result<Option<Vec<u8>>, ProjectDataError> {
+    return match fs::read(path) {
+        Ok(content) if content.is_empty() => Ok(None),
+        Ok(content) => Ok(Some(content)),
+        Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).context(ReadDataFile { path })
+    }
+}
+
+fn decode(path: &String, encoding: &Encoding, content: &[u8]) -> Result<ProjectData, ProjectDataError> {
+    let value = decode_value(encoding, content).context(DecodeDataFile { path })?;
+
+    return Ok(schema_migrations::migrate(path, value)?.data);
+}
+
+/// YAML is read into a generic value, so migrations can handle any earlier layout.
+/// RON and bincode can not be read without knowing the layout, they are tried with the layouts of all schema versions.
+fn decode_value(encoding: &Encoding, content: &[u8]) -> Result<Value, DeSerError> {
+    return match encoding {
+        Encoding::Yaml => Yaml.deserialize(content),
+        Encoding::Bin => decode_known_layouts(&Bincode, content),
+        Encoding::Ron => decode_known_layouts(&Ron, content)
+    }
+}
+
+/// Newest layout first, the oldest one's error is returned when none fits.
+fn decode_known_layouts<L: LayoutDecoder>(decoder: &L, content: &[u8]) -> Result<Value, DeSerError> {
+    let value = if let Ok(versioned) = decoder.decode::<VersionedProjectData>(content) {
+        serde_json::to_value(versioned)
+    } else if let Ok(versioned) = decoder.decode::<VersionedProjectDataV9>(content) {
+        serde_json::to_value(versioned)
+    } else if let Ok(versioned) = decoder.decode::<VersionedProjectDataV7>(content) {
+        serde_json::to_value(versioned)
+    } else if let Ok(versioned) = decoder.decode::<VersionedProjectDataV6>(content) {
+        serde_json::to_value(versioned)
+    } else if let Ok(versioned) = decoder.decode::<VersionedProjectDataV5>(content) {
+        serde_json::to_value(versioned)
+    } else if let Ok(versioned) = decoder.decode::<VersionedProjectDataV4>(content) {
+        serde_json::to_value(versioned)
+    } else if let Ok(versioned) = decoder.decode::<VersionedProjectDataV3>(content) {
+        serde_json::to_value(versioned)
+    } else if let Ok(versioned) = decoder.decode::<VersionedProjectDataV2>(content) {
+        serde_json::to_value(versioned)
+    } else if let Ok(versioned) = decoder.decode::<VersionedProjectDataV1>(content) {
+        serde_json::to_value(versioned)
+    } else {
+        serde_json::to_value(decoder.decode::<ProjectDataV1>(content)?)
+    };
+
+    return value.map_err(|e| DeSerError::Internal(e.to_string()));
+}
+
+trait LayoutDecoder {
+    fn decode<T: Serialize + DeserializeOwned>(&self, content: &[u8]) -> Result<T, DeSerError>;
+}
+
+impl LayoutDecoder for Ron {
+    fn decode<T: Serialize + DeserializeOwned>(&self, content: &[u8]) -> Result<T, DeSerError> {
+        return self.deserialize(content);
+    }
+}
+
+impl LayoutDecoder for Bincode {
+    /// Same options as rustbreak's Bincode, but the whole content has to be used so that the layouts can be told apart,
+    /// and limited so that garbage length prefixes can not allocate.
+    fn decode<T: Serialize + DeserializeOwned>(&self, content: &[u8]) -> Result<T, DeSerError> {
+        return bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .with_limit(content.len() as u64)
+            .deserialize(content)
+            .map_err(DeSerError::from);
+    }
+}
+
+impl<D: DeSerializer<VersionedProjectData>> ProjectStore for RustbreakStore<D> {
+    fn get_project(&self, project_id: &String) -> Result<Option<Project>, ProjectDataError> {
+        return self.db.read(|db| db.data.projects.get(project_id).cloned()).context(Database);
+    }
+
+    fn get_projects(&self) -> Result<HashMap<String, Project>, ProjectDataError> {
+        return self.db.read(|db| db.data.projects.clone()).context(Database);
+    }
+
+    fn insert_project(&self, project_id: &String, project: Project) -> Result<(), ProjectDataError> {
+        return self.db.write(|db| {
+            db.data.projects.insert(project_id.to_string(), project);
+        }).context(Database);
+    }
+
+    fn insert_task(&self, project_id: &String, task_name: &String) -> Result<bool, ProjectDataError> {
+        return self.db.write(|db| {
+            if let Some(p) = db.data.projects.get_mut(project_id) {
+                p.tasks.insert(task_name.to_string());
+                return true
+            }
+            return false
+        }).context(Database);
+    }
+
+    fn get_entries(&self, project_id: &String) -> Result<Vec<BillableEntry>, ProjectDataError> {
+        return self.db.read(|db| {
+            db.data.billable.iter()
+                .filter(|e| &e.project_id == project_id)
+                .cloned()
+                .collect()
+        }).context(Database);
+    }
+
+    fn insert_entry(&self, entry: BillableEntry) -> Result<(), ProjectDataError> {
+        return self.db.write(|db| {
+            db.data.push_entry(entry);
+        }).context(Database);
+    }
+
+    fn next_entry_id(&self) -> Result<u64, ProjectDataError> {
+        return self.db.read(|db| db.data.next_entry_id()).context(Database);
+    }
+
+    fn remove_entry(&self, entry: &BillableEntry) -> Result<bool, ProjectDataError> {
+        return self.db.write(|db| {
+            match db.data.billable.iter().rposition(|e| e == entry) {
+                Some(index) => {
+                    db.data.billable.remove(index);
+                    return true
+                },
+                None => return false
+            }
+        }).context(Database);
+    }
+
+    fn get_selected_project(&self) -> Result<Option<String>, ProjectDataError> {
+        return self.db.read(|db| db.data.selected_project.clone()).context(Database);
+    }
+
+    fn set_selected_project(&self, project_id: &String) -> Result<(), ProjectDataError> {
+        return self.db.write(|db| {
+            db.data.selected_project = Some(project_id.to_string());
+        }).context(Database);
+    }
+
+    fn get_invoice_sequence(&self) -> Result<InvoiceSequence, ProjectDataError> {
+        return self.db.read(|db| db.data.invoice_numbers.clone()).context(Database);
+    }
+
+    fn set_invoice_sequence(&self, sequence: InvoiceSequence) -> Result<(), ProjectDataError> {
+        return self.db.write(|db| {
+            db.data.invoice_numbers = sequence;
+        }).context(Database);
+    }
+
+    fn clear_selected_project(&self) -> Result<(), ProjectDataError> {
+        return self.db.write(|db| {
+            db.data.selected_project = None;
+        }).context(Database);
+    }
+
+    fn get_invoices(&self) -> Result<Vec<Invoice>, ProjectDataError> {
+        return self.db.read(|db| db.data.invoices.clone()).context(Database);
+    }
+
+    fn insert_invoice(&self, invoice: Invoice) -> Result<(), ProjectDataError> {
+        return self.db.write(|db| {
+            match db.data.invoices.iter_mut().find(|i| i.number == invoice.number) {
+                Some(existing) => *existing = invoice,
+                None => db.data.invoices.push(invoice)
+            }
+        }).context(Database);
+    }
+
+    fn snapshot(&self) -> Result<ProjectData, ProjectDataError> {
+        return self.db.read(|db| db.data.clone()).context(Database);
+    }
+
+    fn replace(&self, data: ProjectData) -> Result<(), ProjectDataError> {
+        return self.db.put_data(schema_migrations::current(data), false).context(Database);
+    }
+
+    fn backup(&self, target: &String) -> Result<(), ProjectDataError> {
+        // Nothing has been saved yet
+        if detect_encoding(&self.path)?.is_none() {
+            return Ok(())
+        }
+
+        fs::copy(&self.path, target).context(WriteDataFile { path: target })?;
+
+        return Ok(());
+    }
+
+    fn save(&self) -> Result<(), ProjectDataError> {
+        return self.db.save().context(Database);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+    use std::env;
+    use crate::domain::objects::invoice::{InvoiceStatus, Submission};
+    use crate::domain::objects::project_data::BillableUnit::Hour;
+    use crate::domain::objects::project_data::LineGrouping;
+
+    fn mock_data() -> ProjectData {
+        let mut data = ProjectData::default();
+        data.projects.insert("foo".to_string(), Project { name: "Foo".to_string(), unit_price: 80, unit: Hour, tasks: HashSet::new(), aliases: vec![], address: vec![], grouping: LineGrouping::Task });
+        data.billable.push(BillableEntry {
+            id: 1,
+            project_id: "foo".to_string(),
+            task: "development".to_string(),
+            quantity: 7.5,
+            date: "2020-10-11 22:09:24.269707 +02:00".to_string()
+        });
+
+        return data;
+    }
+
+    const DB_FILE: &str = "test_helpers/db.yaml";
+
+    fn temp_db_path(name: &str) -> String {
+        let path = env::temp_dir().join(format!("hours-rustbreak-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+
+        return path.to_string_lossy().to_string();
+    }
+
+    #[test]
+    fn test_load_existing_file() {
+        let store = RustbreakStore::<Yaml>::new(DB_FILE.to_string()).unwrap();
+
+        assert_eq!(store.get_entries(&"foo".to_string()).unwrap(), vec![]);
+        assert_eq!(store.get_selected_project().unwrap(), None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = temp_db_path("save_and_load");
+        let project = Project { name: "Foo".to_string(), unit_price: 80, unit: Hour, tasks: HashSet::new(), aliases: vec![], address: vec![], grouping: LineGrouping::Task };
+
+        let store = RustbreakStore::<Yaml>::new(path.clone()).unwrap();
+        store.insert_project(&"foo".to_string(), project.clone()).unwrap();
+        store.save().unwrap();
+        drop(store);
+
+        let reloaded = RustbreakStore::<Yaml>::new(path.clone()).unwrap();
+
+        assert_eq!(reloaded.get_project(&"foo".to_string()).unwrap(), Some(project));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_detect_encoding() {
+        for encoding in DETECTION_ORDER.iter() {
+            let path = temp_db_path(&format!("detect_{:?}", encoding));
+            let store = open_as(path.clone(), encoding.clone()).unwrap();
+            store.replace(mock_data()).unwrap();
+            store.save().unwrap();
+            drop(store);
+
+            assert_eq!(detect_encoding(&path).unwrap(), Some(encoding.clone()));
+            assert_eq!(open(path.clone(), Encoding::Yaml).unwrap().snapshot().unwrap(), mock_data());
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    #[test]
+    fn test_detect_encoding_missing_file() {
+        let path = temp_db_path("detect_missing");
+
+        assert_eq!(detect_encoding(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn test_detect_encoding_unknown() {
+        let res = detect_encoding(&"test_helpers/file.txt".to_string());
+
+        assert_eq!(res.is_err(), true);
+    }
+
+    #[test]
+    fn test_load_unversioned_files() {
+        let legacy: ProjectDataV1 = serde_json::from_value(serde_json::to_value(mock_data()).unwrap()).unwrap();
+
+        for encoding in DETECTION_ORDER.iter() {
+            let path = temp_db_path(&format!("unversioned_{:?}", encoding));
+            let content = match encoding {
+                Encoding::Yaml => Yaml.serialize(&legacy),
+                Encoding::Bin => Bincode.serialize(&legacy),
+                Encoding::Ron => Ron.serialize(&legacy)
+            }.unwrap();
+            fs::write(&path, content).unwrap();
+
+            assert_eq!(check_schema(&path).unwrap(), Some(0));
+            assert_eq!(open(path.clone(), Encoding::Yaml).unwrap().snapshot().unwrap(), mock_data());
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    /// Writes `data` in the layout `L` of `version` and checks that it loads unchanged.
+    fn assert_loads_version<L: Serialize + DeserializeOwned>(version: u64, data: ProjectData) {
+        let mut value = serde_json::to_value(schema_migrations::current(data.clone())).unwrap();
+        value["version"] = serde_json::json!(version);
+        let legacy: L = serde_json::from_value(value).unwrap();
+
+        for encoding in DETECTION_ORDER.iter() {
+            let path = temp_db_path(&format!("version_{}_{:?}", version, encoding));
+            let content = match encoding {
+                Encoding::Yaml => Yaml.serialize(&legacy),
+                Encoding::Bin => Bincode.serialize(&legacy),
+                Encoding::Ron => Ron.serialize(&legacy)
+            }.unwrap();
+            fs::write(&path, content).unwrap();
+
+            assert_eq!(check_schema(&path).unwrap(), Some(version));
+            assert_eq!(open(path.clone(), Encoding::Yaml).unwrap().snapshot().unwrap(), data);
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    #[test]
+    fn test_load_older_versions() {
+        let mut with_invoice = mock_data();
+        with_invoice.invoices.push(Invoice {
+            number: "RE-2020-0001".to_string(),
+            cancels: None,
+            project_id: "foo".to_string(),
+            customer: vec!["Foo GmbH".to_string()],
+            date: "2020-10-31".to_string(),
+            service_period: None,
+            line_items: vec![],
+            net: 0,
+            tax_rate: 19.0,
+            tax: 0,
+            gross: 0,
+            status: InvoiceStatus::Draft,
+            lex_office_id: None,
+            lex_office_number: None,
+            submission: None,
+            entries: vec![1]
+        });
+
+        assert_loads_version::<VersionedProjectDataV2>(2, mock_data());
+        assert_loads_version::<VersionedProjectDataV3>(3, mock_data());
+        assert_loads_version::<VersionedProjectDataV4>(4, mock_data());
+        assert_loads_version::<VersionedProjectDataV5>(5, with_invoice.clone());
+        assert_loads_version::<VersionedProjectDataV6>(6, with_invoice.clone());
+        assert_loads_version::<VersionedProjectDataV7>(7, with_invoice.clone());
+        with_invoice.invoices[0].submission = Some(Submission { queued: "2020-10-31 10:00:00 +01:00".to_string(), attempts: 1, last_error: None, rejected: true });
+        with_invoice.last_entry_id = 1;
+        assert_loads_version::<VersionedProjectDataV9>(8, with_invoice.clone());
+        assert_loads_version::<VersionedProjectDataV9>(9, with_invoice);
+    }
+
+    #[test]
+    fn test_unreadable_file_fails_to_load() {
+        let path = temp_db_path("unreadable");
+        fs::write(&path, "billable: [").unwrap();
+
+        assert_eq!(RustbreakStore::<Yaml>::new(path.clone()).is_err(), true);
+        let _ = fs::remove_file(&path);
+    }
+}
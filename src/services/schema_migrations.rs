@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use serde_json::{json, Value};
+use snafu::ResultExt;
+
+use crate::domain::errors::project_data::{ProjectDataError, MigrateSchema, UnsupportedSchemaVersion};
+use crate::domain::objects::project_data::{ProjectData, VersionedProjectData};
+use crate::services::slug_service::SlugService;
+
+
+pub struct Migration {
+    pub description: &'static str,
+    apply: fn(Value) -> Value
+}
+
+/// Migrations of the stored `ProjectData`, `MIGRATIONS[n]` turns version `n` into version `n + 1`.
+/// Version 0 is the bare `ProjectData` written before the data was versioned.
+const MIGRATIONS: &[Migration] = &[
+    Migration { description: "wrap the project data in a versioned envelope", apply: wrap_in_envelope },
+    Migration { description: "add aliases to projects", apply: add_project_aliases },
+    Migration { description: "add customer addresses to projects", apply: add_project_addresses },
+    Migration { description: "add the invoice number sequence", apply: add_invoice_numbers },
+    Migration { description: "add entry ids and the invoices collection", apply: add_entry_ids_and_invoices },
+    Migration { description: "add cancellation references to invoices", apply: add_invoice_cancellations },
+    Migration { description: "add line grouping to projects", apply: add_project_grouping },
+    Migration { description: "add LexOffice submissions to invoices", apply: add_invoice_submissions },
+    Migration { description: "turn legacy project ids into slugs", apply: slug_project_ids },
+    Migration { description: "add LexOffice numbers to invoices", apply: add_invoice_lex_office_numbers }
+];
+
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Schema version of stored data, data without an envelope is version 0.
+pub fn version_of(value: &Value) -> u64 {
+    return match (value.get("version"), value.get("data")) {
+        (Some(version), Some(_)) => version.as_u64().unwrap_or(0),
+        _ => 0
+    }
+}
+
+/// Migrations still to be applied to data in `version`.
+pub fn pending(version: u64) -> &'static [Migration] {
+    return MIGRATIONS.get(version as usize..).unwrap_or(&[]);
+}
+
+/// Applies all pending migrations to the data read from `path`.
+pub fn migrate(path: &String, value: Value) -> Result<VersionedProjectData, ProjectDataError> {
+    let version = version_of(&value);
+
+    if version > CURRENT_VERSION as u64 {
+        return UnsupportedSchemaVersion { path, version, supported: CURRENT_VERSION }.fail()
+    }
+
+    let migrated = pending(version).iter().fold(value, |value, migration| (migration.apply)(value));
+
+    return serde_json::from_value(migrated).context(MigrateSchema { path, version: CURRENT_VERSION });
+}
+
+/// Wraps data of a file written in the current schema version.
+pub fn current(data: ProjectData) -> VersionedProjectData {
+    return VersionedProjectData {
+        version: CURRENT_VERSION,
+        data
+    }
+}
+
+fn wrap_in_envelope(value: Value) -> Value {
+    return json!({ "version": 1, "data": value });
+}
+
+fn add_project_aliases(mut value: Value) -> Value {
+    if let Some(projects) = value.pointer_mut("/data/projects").and_then(Value::as_object_mut) {
+        for project in projects.values_mut().filter_map(Value::as_object_mut) {
+            project.entry("aliases").or_insert_with(|| json!([]));
+        }
+    }
+    value["version"] = json!(2);
+
+    return value;
+}
+
+fn add_project_addresses(mut value: Value) -> Value {
+    if let Some(projects) = value.pointer_mut("/data/projects").and_then(Value::as_object_mut) {
+        for project in projects.values_mut().filter_map(Value::as_object_mut) {
+            project.entry("address").or_insert_with(|| json!([]));
+        }
+    }
+    value["version"] = json!(3);
+
+    return value;
+}
+
+fn add_invoice_numbers(mut value: Value) -> Value {
+    if let Some(data) = value.get_mut("data").and_then(Value::as_object_mut) {
+        data.entry("invoice_numbers").or_insert_with(|| json!({ "last": 0, "year": null, "used": [] }));
+    }
+    value["version"] = json!(4);
+
+    return value;
+}
+
+fn add_entry_ids_and_invoices(mut value: Value) -> Value {
+    if let Some(billable) = value.pointer_mut("/data/billable").and_then(Value::as_array_mut) {
+        for (index, entry) in billable.iter_mut().filter_map(Value::as_object_mut).enumerate() {
+            entry.insert("id".to_string(), json!(index + 1));
+        }
+    }
+    if let Some(data) = value.get_mut("data").and_then(Value::as_object_mut) {
+        data.entry("invoices").or_insert_with(|| json!([]));
+    }
+    value["version"] = json!(5);
+
+    return value;
+}
+
+fn add_invoice_cancellations(mut value: Value) -> Value {
+    if let Some(invoices) = value.pointer_mut("/data/invoices").and_then(Value::as_array_mut) {
+        for invoice in invoices.iter_mut().filter_map(Value::as_object_mut) {
+            invoice.entry("cancels").or_insert(Value::Null);
+        }
+    }
+    value["version"] = json!(6);
+
+    return value;
+}
+
+fn add_project_grouping(mut value: Value) -> Value {
+    if let Some(projects) = value.pointer_mut("/data/projects").and_then(Value::as_object_mut) {
+        for project in projects.values_mut().filter_map(Value::as_object_mut) {
+            project.entry("grouping").or_insert_with(|| json!("task"));
+        }
+    }
+    value["version"] = json!(7);
+
+    return value;
+}
+
+fn add_invoice_submissions(mut value: Value) -> Value {
+    if let Some(invoices) = value.pointer_mut("/data/invoices").and_then(Value::as_array_mut) {
+        for invoice in invoices.iter_mut().filter_map(Value::as_object_mut) {
+            invoice.entry("submission").or_insert(Value::Null);
+        }
+    }
+    value["version"] = json!(8);
+
+    return value;
+}
+
+fn slug_project_ids(mut value: Value) -> Value {
+    let projects: Vec<(String, String)> = value.pointer("/data/projects").and_then(Value::as_object).map(|projects| projects
+        .iter()
+        .filter_map(|(id, project)| Some((id.to_string(), project.get("name")?.as_str()?.to_string())))
+        .collect()
+    ).unwrap_or_default();
+    let renames: HashMap<String, String> = SlugService::legacy_id_renames(&projects).into_iter().collect();
+    let rename = |id: &mut Value| {
+        if let Some(new_id) = id.as_str().and_then(|old| renames.get(old)) {
+            *id = json!(new_id);
+        }
+    };
+
+    if let Some(projects) = value.pointer_mut("/data/projects").and_then(Value::as_object_mut) {
+        for (old, new) in renames.iter() {
+            if let Some(project) = projects.remove(old) {
+                projects.insert(new.to_string(), project);
+            }
+        }
+    }
+    for collection in ["/data/billable", "/data/invoices"].iter() {
+        if let Some(items) = value.pointer_mut(collection).and_then(Value::as_array_mut) {
+            items.iter_mut().filter_map(|item| item.get_mut("project_id")).for_each(rename);
+        }
+    }
+    if let Some(selected) = value.pointer_mut("/data/selected_project") {
+        rename(selected);
+    }
+    value["version"] = json!(9);
+
+    return value;
+}
+
+fn add_invoice_lex_office_numbers(mut value: Value) -> Value {
+    if let Some(invoices) = value.pointer_mut("/data/invoices").and_then(Value::as_array_mut) {
+        for invoice in invoices.iter_mut().filter_map(Value::as_object_mut) {
+            invoice.entry("lex_office_number").or_insert(Value::Null);
+        }
+    }
+    value["version"] = json!(10);
+
+    return value;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::objects::project_data::LineGrouping;
+
+    #[test]
+    fn test_migrate_unversioned_data() {
+        let value = json!({ "billable": [], "projects": {} });
+
+        let migrated = migrate(&"db".to_string(), value).unwrap();
+
+        assert_eq!(migrated, current(ProjectData::default()));
+    }
+
+    #[test]
+    fn test_migrate_adds_project_aliases() {
+        let value = json!({ "version": 1, "data": {
+            "billable": [
+                { "project_id": "foo", "task": "dev", "quantity": 1.0, "date": "2020-10-11 22:09:24.269707 +02:00" },
+                { "project_id": "foo", "task": "dev", "quantity": 2.0, "date": "2020-10-12 22:09:24.269707 +02:00" }
+            ],
+            "projects": { "foo": { "name": "Foo", "unit_price": 80, "unit": "hour", "tasks": [] } }
+        }});
+
+        let migrated = migrate(&"db".to_string(), value).unwrap();
+
+        assert_eq!(migrated.version, CURRENT_VERSION);
+        assert_eq!(migrated.data.projects["foo"].aliases, Vec::<String>::new());
+        assert_eq!(migrated.data.projects["foo"].address, Vec::<String>::new());
+        assert_eq!(migrated.data.projects["foo"].grouping, LineGrouping::Task);
+        assert_eq!(migrated.data.invoice_numbers, Default::default());
+        assert_eq!(migrated.data.billable.iter().map(|e| e.id).collect::<Vec<u64>>(), vec![1, 2]);
+        assert_eq!(migrated.data.invoices, vec![]);
+    }
+
+    #[test]
+    fn test_migrate_slugs_legacy_project_ids() {
+        let value = json!({ "version": 8, "data": {
+            "billable": [{ "id": 1, "project_id": "foo bar", "task": "dev", "quantity": 1.0, "date": "2020-10-11 22:09:24.269707 +02:00" }],
+            "projects": {
+                "foo bar": { "name": "Foo Bar", "unit_price": 80, "unit": "hour", "tasks": [], "aliases": [], "address": [], "grouping": "task" },
+                "foo-bar": { "name": "Other", "unit_price": 80, "unit": "hour", "tasks": [], "aliases": [], "address": [], "grouping": "task" }
+            },
+            "selected_project": "foo bar",
+            "invoice_numbers": { "last": 0, "year": null, "used": [] },
+            "invoices": []
+        }});
+
+        let migrated = migrate(&"db".to_string(), value).unwrap();
+
+        assert_eq!(migrated.data.projects["foo-bar-2"].name, "Foo Bar");
+        assert_eq!(migrated.data.projects["foo-bar"].name, "Other");
+        assert_eq!(migrated.data.billable[0].project_id, "foo-bar-2");
+        assert_eq!(migrated.data.selected_project, Some("foo-bar-2".to_string()));
+    }
+
+    #[test]
+    fn test_migrate_current_data() {
+        let value = serde_json::to_value(current(ProjectData::default())).unwrap();
+
+        assert_eq!(version_of(&value), CURRENT_VERSION as u64);
+        assert_eq!(pending(CURRENT_VERSION as u64).len(), 0);
+        assert_eq!(migrate(&"db".to_string(), value).unwrap(), current(ProjectData::default()));
+    }
+
+    #[test]
+    fn test_migrate_newer_version_fails() {
+        let value = json!({ "version": CURRENT_VERSION + 1, "data": {} });
+
+        assert_eq!(migrate(&"db".to_string(), value).is_err(), true);
+    }
+}
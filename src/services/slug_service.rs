@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+const FALLBACK_SLUG: &str = "project";
+
+pub struct SlugService;
+
+impl SlugService {
+    /// Turns a name into a lowercase identifier, e.g. "Café GmbH" into "cafe-gmbh".
+    /// Accents are dropped after compatibility decomposition, every other run of non alphanumeric characters becomes a dash.
+    pub fn slugify(name: &str) -> String {
+        let mut slug = String::new();
+        let mut pending_dash = false;
+
+        for c in name.nfkd().filter(|c| !is_combining_mark(*c)).flat_map(char::to_lowercase) {
+            if c.is_alphanumeric() {
+                if pending_dash && !slug.is_empty() {
+                    slug.push('-');
+                }
+                pending_dash = false;
+                slug.push(c);
+            } else {
+                pending_dash = true;
+            }
+        }
+
+        if slug.is_empty() {
+            return FALLBACK_SLUG.to_string()
+        }
+
+        return slug;
+    }
+
+    /// `slug` itself if `is_taken` does not hold for it, otherwise the first of `slug-2`, `slug-3`, … that is free.
+    pub fn unique(slug: &str, is_taken: impl Fn(&str) -> bool) -> String {
+        if !is_taken(slug) {
+            return slug.to_string()
+        }
+
+        return (2..).map(|n| format!("{}-{}", slug, n)).find(|candidate| !is_taken(candidate)).unwrap();
+    }
+
+    /// New ids of the `(id, name)` pairs of projects created before ids were slugs, which have their lowercased name as id.
+    /// Ids that are already taken get a suffix, renames are returned as `(old, new)` ordered by the old id.
+    pub fn legacy_id_renames(projects: &[(String, String)]) -> Vec<(String, String)> {
+        let mut legacy: Vec<&(String, String)> = projects.iter()
+            .filter(|(id, name)| *id == name.to_ascii_lowercase() && *id != SlugService::slugify(name))
+            .collect();
+        legacy.sort();
+
+        let mut taken: HashSet<String> = projects.iter().map(|(id, _)| id.to_string()).collect();
+        let mut renames = vec![];
+        for (id, name) in legacy {
+            let new_id = SlugService::unique(&SlugService::slugify(name), |candidate| taken.contains(candidate));
+            taken.insert(new_id.to_string());
+            renames.push((id.to_string(), new_id));
+        }
+
+        return renames;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_slugify_normalizes_case_and_accents() {
+        assert_eq!(SlugService::slugify("Café GmbH"), "cafe-gmbh");
+        assert_eq!(SlugService::slugify("café gmbh"), "cafe-gmbh");
+        assert_eq!(SlugService::slugify("CAFE\u{301}  GMBH"), "cafe-gmbh");
+    }
+
+    #[test]
+    fn test_slugify_separators() {
+        assert_eq!(SlugService::slugify(" -Foo_Bar & Co.- "), "foo-bar-co");
+        assert_eq!(SlugService::slugify("ﬁle №1"), "file-no1");
+        assert_eq!(SlugService::slugify("!!!"), "project");
+    }
+
+    #[test]
+    fn test_unique_adds_suffix() {
+        assert_eq!(SlugService::unique("foo", |_| false), "foo");
+        assert_eq!(SlugService::unique("foo", |slug| slug == "foo" || slug == "foo-2"), "foo-3");
+    }
+
+    #[test]
+    fn test_legacy_id_renames() {
+        let projects = vec![
+            ("foo bar".to_string(), "Foo Bar".to_string()),
+            ("foo-bar".to_string(), "Foo-Bar".to_string()),
+            ("café".to_string(), "Café".to_string()),
+            ("baz".to_string(), "Baz".to_string()),
+            ("acme".to_string(), "Renamed".to_string())
+        ];
+
+        assert_eq!(SlugService::legacy_id_renames(&projects), vec![
+            ("café".to_string(), "cafe".to_string()),
+            ("foo bar".to_string(), "foo-bar-2".to_string())
+        ]);
+    }
+}
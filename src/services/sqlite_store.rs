@@ -0,0 +1,938 @@
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Transaction, NO_PARAMS};
+use rusqlite::types::Type;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use snafu::ResultExt;
+use tempfile::NamedTempFile;
+
+use crate::constants::DATABASE_LOCK_TIMEOUT_SECS;
+use crate::domain::errors::project_data::{ProjectDataError, CreateDataDirectory, NotADatabase, ReadDataFile, Sqlite};
+use crate::domain::objects::invoice::{Invoice, InvoiceStatus, LineItem, ServicePeriod, Submission};
+use crate::domain::objects::project_data::{BillableEntry, BillableUnit, InvoiceSequence, LineGrouping, Project, ProjectData};
+use crate::services::project_store::ProjectStore;
+use crate::services::slug_service::SlugService;
+
+const SELECTED_PROJECT_KEY: &str = "selected_project";
+const LAST_ENTRY_ID_KEY: &str = "last_entry_id";
+const INVOICE_SEQUENCE_LAST_KEY: &str = "invoice_sequence_last";
+const INVOICE_SEQUENCE_YEAR_KEY: &str = "invoice_sequence_year";
+
+/// Step of the migrations, either SQL or a change of the data that SQL can not express.
+enum Migration {
+    Schema(&'static str),
+    Data(fn(&Transaction) -> rusqlite::Result<()>)
+}
+
+/// Migrations, applied in order. The number of applied migrations is kept in `PRAGMA user_version`.
+const MIGRATIONS: &[Migration] = &[
+    Migration::Schema("CREATE TABLE projects (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        unit_price INTEGER NOT NULL,
+        unit TEXT NOT NULL
+    );
+    CREATE TABLE tasks (
+        project_id TEXT NOT NULL REFERENCES projects(id),
+        name TEXT NOT NULL,
+        PRIMARY KEY (project_id, name)
+    );
+    CREATE TABLE billable_entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        project_id TEXT NOT NULL REFERENCES projects(id),
+        task TEXT NOT NULL,
+        quantity REAL NOT NULL,
+        date TEXT NOT NULL
+    );
+    CREATE INDEX idx_billable_entries_project ON billable_entries(project_id);
+    CREATE INDEX idx_billable_entries_date ON billable_entries(date);
+    CREATE TABLE settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );"),
+    Migration::Schema("CREATE TABLE project_aliases (
+        alias TEXT PRIMARY KEY,
+        project_id TEXT NOT NULL REFERENCES projects(id)
+    );"),
+    Migration::Schema("ALTER TABLE projects ADD COLUMN address TEXT NOT NULL DEFAULT '';"),
+    Migration::Schema("CREATE TABLE invoice_numbers (
+        number TEXT PRIMARY KEY
+    );"),
+    Migration::Schema("CREATE TABLE invoices (
+        number TEXT PRIMARY KEY,
+        project_id TEXT NOT NULL,
+        customer TEXT NOT NULL,
+        date TEXT NOT NULL,
+        service_start TEXT,
+        service_end TEXT,
+        net INTEGER NOT NULL,
+        tax_rate REAL NOT NULL,
+        tax INTEGER NOT NULL,
+        gross INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        lex_office_id TEXT
+    );
+    CREATE TABLE invoice_line_items (
+        invoice_number TEXT NOT NULL REFERENCES invoices(number),
+        position INTEGER NOT NULL,
+        description TEXT NOT NULL,
+        quantity REAL NOT NULL,
+        unit TEXT NOT NULL,
+        unit_price INTEGER NOT NULL,
+        PRIMARY KEY (invoice_number, position)
+    );
+    CREATE TABLE invoice_entries (
+        invoice_number TEXT NOT NULL REFERENCES invoices(number),
+        entry_id INTEGER NOT NULL,
+        PRIMARY KEY (invoice_number, entry_id)
+    );"),
+    Migration::Schema("ALTER TABLE invoices ADD COLUMN cancels TEXT;"),
+    Migration::Schema("ALTER TABLE projects ADD COLUMN grouping TEXT NOT NULL DEFAULT 'task';"),
+    Migration::Schema("ALTER TABLE invoices ADD COLUMN submission_queued TEXT;
+    ALTER TABLE invoices ADD COLUMN submission_attempts INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE invoices ADD COLUMN submission_error TEXT;"),
+    Migration::Schema("ALTER TABLE invoices ADD COLUMN submission_rejected INTEGER NOT NULL DEFAULT 0;"),
+    Migration::Data(slug_project_ids),
+    Migration::Schema("ALTER TABLE invoices ADD COLUMN lex_office_number TEXT;")
+];
+
+/// Tables every migrated database has.
+const TABLES: &[&str] = &["projects", "tasks", "billable_entries", "settings", "project_aliases", "invoice_numbers", "invoices", "invoice_line_items", "invoice_entries"];
+
+/// Turns the ids of projects created before ids were slugs into slugs, see `SlugService::legacy_id_renames`.
+fn slug_project_ids(transaction: &Transaction) -> rusqlite::Result<()> {
+    // References are checked once all of them point to the new ids
+    transaction.execute_batch("PRAGMA defer_foreign_keys = ON")?;
+    let projects = transaction
+        .prepare("SELECT id, name FROM projects")?
+        .query_map(NO_PARAMS, |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(String, String)>, rusqlite::Error>>()?;
+
+    for (old, new) in SlugService::legacy_id_renames(&projects) {
+        for table in ["projects", "tasks", "billable_entries", "project_aliases", "invoices"].iter() {
+            let column = if *table == "projects" { "id" } else { "project_id" };
+            transaction.execute(&format!("UPDATE {} SET {} = ?2 WHERE {} = ?1", table, column, column), params![old, new])?;
+        }
+        transaction.execute("UPDATE settings SET value = ?3 WHERE key = ?1 AND value = ?2", params![SELECTED_PROJECT_KEY, old, new])?;
+    }
+
+    return Ok(());
+}
+
+
+/// Store backed by a SQLite database. All changes happen in one transaction that is committed by `save`,
+/// the transaction also keeps other hours processes from writing until then.
+pub struct SqliteStore {
+    connection: Connection,
+    path: String
+}
+
+impl SqliteStore {
+    pub fn new(path: String) -> Result<SqliteStore, ProjectDataError> {
+        if let Some(parent) = Path::new(&path).parent() {
+            fs::create_dir_all(parent).context(CreateDataDirectory { path: parent.to_string_lossy() })?;
+        }
+
+        let mut connection = Connection::open(&path).context(Sqlite)?;
+        // Concurrent writers wait for each other instead of failing right away
+        connection.busy_timeout(Duration::from_secs(DATABASE_LOCK_TIMEOUT_SECS)).context(Sqlite)?;
+        SqliteStore::migrate_to(&mut connection, MIGRATIONS.len())?;
+        connection.execute_batch("BEGIN IMMEDIATE").context(Sqlite)?;
+
+        return Ok(SqliteStore {
+            connection,
+            path
+        })
+    }
+
+    /// Opens the database at `path` for reading only: it is neither locked, migrated nor written to.
+    /// Fails unless the database is at the current schema version and has all of its tables.
+    pub fn open_read_only(path: &String) -> Result<SqliteStore, ProjectDataError> {
+        let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).context(Sqlite)?;
+        let version = SqliteStore::user_version(&connection)?;
+        if version != MIGRATIONS.len() as i64 {
+            return NotADatabase { path, reason: format!("schema version {}, expected {}", version, MIGRATIONS.len()) }.fail()
+        }
+
+        let mut statement = connection.prepare("SELECT name FROM sqlite_master WHERE type = 'table'").context(Sqlite)?;
+        let tables = statement
+            .query_map(NO_PARAMS, |row| row.get(0))
+            .context(Sqlite)?
+            .collect::<Result<HashSet<String>, rusqlite::Error>>()
+            .context(Sqlite)?;
+        drop(statement);
+        if let Some(missing) = TABLES.iter().find(|table| !tables.contains(**table)) {
+            return NotADatabase { path, reason: format!("the {} table is missing", missing) }.fail()
+        }
+
+        return Ok(SqliteStore {
+            connection,
+            path: path.to_string()
+        })
+    }
+
+    /// Data of the database at `path`, e.g. a backup, which is left as it is.
+    /// Databases at an older schema version are migrated in a temporary copy.
+    pub fn read_snapshot(path: &String) -> Result<ProjectData, ProjectDataError> {
+        let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).context(Sqlite)?;
+        let version = SqliteStore::user_version(&connection)?;
+        drop(connection);
+
+        if version > 0 && version < MIGRATIONS.len() as i64 {
+            let copy = NamedTempFile::new().context(ReadDataFile { path })?;
+            fs::copy(path, copy.path()).context(ReadDataFile { path })?;
+            let copy_path = copy.path().to_string_lossy().to_string();
+            // Opening migrates the copy
+            SqliteStore::new(copy_path.clone())?;
+
+            return SqliteStore::open_read_only(&copy_path)?.snapshot()
+        }
+
+        return SqliteStore::open_read_only(path)?.snapshot();
+    }
+
+    /// Number of migrations the database at `path` still needs, `None` when there is no database yet.
+    pub fn pending_migrations(path: &String) -> Result<Option<usize>, ProjectDataError> {
+        if !Path::new(path).exists() {
+            return Ok(None)
+        }
+
+        let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).context(Sqlite)?;
+        let version = SqliteStore::user_version(&connection)?;
+
+        return Ok(Some(MIGRATIONS.len().saturating_sub(version as usize)));
+    }
+
+    fn user_version(connection: &Connection) -> Result<i64, ProjectDataError> {
+        return connection.query_row("PRAGMA user_version", NO_PARAMS, |row| row.get(0)).context(Sqlite);
+    }
+
+    /// Applies the migrations the database still needs to reach `target` migrations.
+    fn migrate_to(connection: &mut Connection, target: usize) -> Result<(), ProjectDataError> {
+        let version = SqliteStore::user_version(connection)?;
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().take(target).skip(version as usize) {
+            let transaction = connection.transaction().context(Sqlite)?;
+            match migration {
+                Migration::Schema(sql) => transaction.execute_batch(sql).context(Sqlite)?,
+                Migration::Data(change) => change(&transaction).context(Sqlite)?
+            }
+            transaction.execute_batch(&format!("PRAGMA user_version = {}", index + 1)).context(Sqlite)?;
+            transaction.commit().context(Sqlite)?;
+        }
+
+        return Ok(());
+    }
+
+    fn unit_to_str(unit: &BillableUnit) -> &'static str {
+        return match unit {
+            BillableUnit::Day => "day",
+            BillableUnit::Hour => "hour"
+        }
+    }
+
+    fn unit_from_str(unit: &str, column: usize) -> Result<BillableUnit, rusqlite::Error> {
+        return match unit {
+            "day" => Ok(BillableUnit::Day),
+            "hour" => Ok(BillableUnit::Hour),
+            _ => Err(SqliteStore::invalid_text(column, "unit", unit))
+        }
+    }
+
+    fn unit_price_from_i64(unit_price: i64, column: usize) -> Result<u16, rusqlite::Error> {
+        return u16::try_from(unit_price).map_err(|_| rusqlite::Error::IntegralValueOutOfRange(column, unit_price));
+    }
+
+    /// Decode error for a text column holding a value hours does not know.
+    fn invalid_text(column: usize, kind: &str, value: &str) -> rusqlite::Error {
+        return rusqlite::Error::FromSqlConversionFailure(column, Type::Text, format!("unknown {} {}", kind, value).into());
+    }
+
+    fn grouping_to_str(grouping: &LineGrouping) -> &'static str {
+        return match grouping {
+            LineGrouping::Task => "task",
+            LineGrouping::Day => "day",
+            LineGrouping::Week => "week",
+            LineGrouping::Entry => "entry",
+            LineGrouping::Single => "single"
+        }
+    }
+
+    fn grouping_from_str(grouping: &str, column: usize) -> Result<LineGrouping, rusqlite::Error> {
+        return match grouping {
+            "task" => Ok(LineGrouping::Task),
+            "day" => Ok(LineGrouping::Day),
+            "week" => Ok(LineGrouping::Week),
+            "entry" => Ok(LineGrouping::Entry),
+            "single" => Ok(LineGrouping::Single),
+            _ => Err(SqliteStore::invalid_text(column, "grouping", grouping))
+        }
+    }
+
+    fn status_to_str(status: &InvoiceStatus) -> &'static str {
+        return match status {
+            InvoiceStatus::Draft => "draft",
+            InvoiceStatus::Sent => "sent",
+            InvoiceStatus::Paid => "paid",
+            InvoiceStatus::Cancelled => "cancelled"
+        }
+    }
+
+    fn status_from_str(status: &str, column: usize) -> Result<InvoiceStatus, rusqlite::Error> {
+        return match status {
+            "draft" => Ok(InvoiceStatus::Draft),
+            "sent" => Ok(InvoiceStatus::Sent),
+            "paid" => Ok(InvoiceStatus::Paid),
+            "cancelled" => Ok(InvoiceStatus::Cancelled),
+            _ => Err(SqliteStore::invalid_text(column, "invoice status", status))
+        }
+    }
+
+    fn get_tasks(&self, project_id: &String) -> Result<HashSet<String>, ProjectDataError> {
+        let mut statement = self.connection
+            .prepare("SELECT name FROM tasks WHERE project_id = ?1")
+            .context(Sqlite)?;
+        let tasks = statement
+            .query_map(params![project_id], |row| row.get(0))
+            .context(Sqlite)?
+            .collect::<Result<HashSet<String>, rusqlite::Error>>()
+            .context(Sqlite)?;
+
+        return Ok(tasks);
+    }
+
+    fn get_aliases(&self, project_id: &String) -> Result<Vec<String>, ProjectDataError> {
+        let mut statement = self.connection
+            .prepare("SELECT alias FROM project_aliases WHERE project_id = ?1 ORDER BY rowid")
+            .context(Sqlite)?;
+        let aliases = statement
+            .query_map(params![project_id], |row| row.get(0))
+            .context(Sqlite)?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()
+            .context(Sqlite)?;
+
+        return Ok(aliases);
+    }
+
+    fn get_project_ids(&self) -> Result<Vec<String>, ProjectDataError> {
+        let mut statement = self.connection.prepare("SELECT id FROM projects").context(Sqlite)?;
+        let ids = statement
+            .query_map(NO_PARAMS, |row| row.get(0))
+            .context(Sqlite)?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()
+            .context(Sqlite)?;
+
+        return Ok(ids);
+    }
+
+    /// Highest entry id given out so far, `0` before the first entry.
+    fn get_last_entry_id(&self) -> Result<u64, ProjectDataError> {
+        let id: i64 = self.connection
+            .query_row("SELECT COALESCE((SELECT CAST(value AS INTEGER) FROM settings WHERE key = ?1), 0)", params![LAST_ENTRY_ID_KEY], |row| row.get(0))
+            .context(Sqlite)?;
+
+        return Ok(id as u64);
+    }
+
+    fn get_setting(&self, key: &str) -> Result<Option<String>, ProjectDataError> {
+        return self.connection
+            .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()
+            .context(Sqlite);
+    }
+
+    fn set_setting(&self, key: &str, value: Option<String>) -> Result<(), ProjectDataError> {
+        match value {
+            Some(value) => self.connection.execute("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)", params![key, value]),
+            None => self.connection.execute("DELETE FROM settings WHERE key = ?1", params![key])
+        }.context(Sqlite)?;
+
+        return Ok(());
+    }
+
+    fn get_all_entries(&self) -> Result<Vec<BillableEntry>, ProjectDataError> {
+        let mut statement = self.connection
+            .prepare("SELECT id, project_id, task, quantity, date FROM billable_entries ORDER BY id")
+            .context(Sqlite)?;
+        let entries = statement
+            .query_map(NO_PARAMS, |row| Ok(BillableEntry {
+                id: row.get::<_, i64>(0)? as u64,
+                project_id: row.get(1)?,
+                task: row.get(2)?,
+                quantity: row.get::<_, f64>(3)? as f32,
+                date: row.get(4)?
+            }))
+            .context(Sqlite)?
+            .collect::<Result<Vec<BillableEntry>, rusqlite::Error>>()
+            .context(Sqlite)?;
+
+        return Ok(entries);
+    }
+
+    fn get_line_items(&self, invoice_number: &String) -> Result<Vec<LineItem>, ProjectDataError> {
+        let mut statement = self.connection
+            .prepare("SELECT description, quantity, unit, unit_price FROM invoice_line_items WHERE invoice_number = ?1 ORDER BY position")
+            .context(Sqlite)?;
+        let line_items = statement
+            .query_map(params![invoice_number], |row| Ok(LineItem {
+                description: row.get(0)?,
+                quantity: row.get::<_, f64>(1)? as f32,
+                unit: SqliteStore::unit_from_str(&row.get::<_, String>(2)?, 2)?,
+                unit_price: SqliteStore::unit_price_from_i64(row.get(3)?, 3)?
+            }))
+            .context(Sqlite)?
+            .collect::<Result<Vec<LineItem>, rusqlite::Error>>()
+            .context(Sqlite)?;
+
+        return Ok(line_items);
+    }
+
+    fn get_invoice_entries(&self, invoice_number: &String) -> Result<Vec<u64>, ProjectDataError> {
+        let mut statement = self.connection
+            .prepare("SELECT entry_id FROM invoice_entries WHERE invoice_number = ?1 ORDER BY entry_id")
+            .context(Sqlite)?;
+        let entries = statement
+            .query_map(params![invoice_number], |row| Ok(row.get::<_, i64>(0)? as u64))
+            .context(Sqlite)?
+            .collect::<Result<Vec<u64>, rusqlite::Error>>()
+            .context(Sqlite)?;
+
+        return Ok(entries);
+    }
+}
+
+impl ProjectStore for SqliteStore {
+    fn get_project(&self, project_id: &String) -> Result<Option<Project>, ProjectDataError> {
+        let row = self.connection
+            .query_row(
+                "SELECT name, unit_price, unit, address, grouping FROM projects WHERE id = ?1",
+                params![project_id],
+                |row| Ok((
+                    row.get::<_, String>(0)?,
+                    SqliteStore::unit_price_from_i64(row.get(1)?, 1)?,
+                    SqliteStore::unit_from_str(&row.get::<_, String>(2)?, 2)?,
+                    row.get::<_, String>(3)?,
+                    SqliteStore::grouping_from_str(&row.get::<_, String>(4)?, 4)?
+                ))
+            )
+            .optional()
+            .context(Sqlite)?;
+
+        return match row {
+            Some((name, unit_price, unit, address, grouping)) => Ok(Some(Project {
+                name,
+                unit_price,
+                unit,
+                tasks: self.get_tasks(project_id)?,
+                aliases: self.get_aliases(project_id)?,
+                address: address.lines().map(String::from).collect(),
+                grouping
+            })),
+            None => Ok(None)
+        }
+    }
+
+    fn get_projects(&self) -> Result<HashMap<String, Project>, ProjectDataError> {
+        let mut projects = HashMap::new();
+
+        for project_id in self.get_project_ids()? {
+            if let Some(project) = self.get_project(&project_id)? {
+                projects.insert(project_id, project);
+            }
+        }
+
+        return Ok(projects);
+    }
+
+    fn insert_project(&self, project_id: &String, project: Project) -> Result<(), ProjectDataError> {
+        self.connection.execute(
+            "INSERT INTO projects (id, name, unit_price, unit, address, grouping) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(id) DO UPDATE SET name = excluded.name, unit_price = excluded.unit_price, unit = excluded.unit,
+             address = excluded.address, grouping = excluded.grouping",
+            params![
+                project_id,
+                project.name,
+                project.unit_price as i64,
+                SqliteStore::unit_to_str(&project.unit),
+                project.address.join("\n"),
+                SqliteStore::grouping_to_str(&project.grouping)
+            ]
+        ).context(Sqlite)?;
+
+        for task in project.tasks.iter() {
+            self.insert_task(project_id, task)?;
+        }
+        self.connection.execute("DELETE FROM project_aliases WHERE project_id = ?1", params![project_id]).context(Sqlite)?;
+        for alias in project.aliases.iter() {
+            self.connection.execute(
+                "INSERT INTO project_aliases (alias, project_id) VALUES (?1, ?2)",
+                params![alias, project_id]
+            ).context(Sqlite)?;
+        }
+
+        return Ok(());
+    }
+
+    fn insert_task(&self, project_id: &String, task_name: &String) -> Result<bool, ProjectDataError> {
+        if self.get_project(project_id)?.is_none() {
+            return Ok(false)
+        }
+
+        self.connection.execute(
+            "INSERT OR IGNORE INTO tasks (project_id, name) VALUES (?1, ?2)",
+            params![project_id, task_name]
+        ).context(Sqlite)?;
+
+        return Ok(true)
+    }
+
+    fn get_entries(&self, project_id: &String) -> Result<Vec<BillableEntry>, ProjectDataError> {
+        let mut statement = self.connection
+            .prepare("SELECT id, project_id, task, quantity, date FROM billable_entries WHERE project_id = ?1 ORDER BY id")
+            .context(Sqlite)?;
+        let entries = statement
+            .query_map(params![project_id], |row| Ok(BillableEntry {
+                id: row.get::<_, i64>(0)? as u64,
+                project_id: row.get(1)?,
+                task: row.get(2)?,
+                quantity: row.get::<_, f64>(3)? as f32,
+                date: row.get(4)?
+            }))
+            .context(Sqlite)?
+            .collect::<Result<Vec<BillableEntry>, rusqlite::Error>>()
+            .context(Sqlite)?;
+
+        return Ok(entries);
+    }
+
+    fn insert_entry(&self, entry: BillableEntry) -> Result<(), ProjectDataError> {
+        self.connection.execute(
+            "INSERT INTO billable_entries (id, project_id, task, quantity, date) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![entry.id as i64, entry.project_id, entry.task, entry.quantity as f64, entry.date]
+        ).context(Sqlite)?;
+        if entry.id > self.get_last_entry_id()? {
+            self.set_setting(LAST_ENTRY_ID_KEY, Some(entry.id.to_string()))?;
+        }
+
+        return Ok(());
+    }
+
+    fn next_entry_id(&self) -> Result<u64, ProjectDataError> {
+        let id: i64 = self.connection
+            .query_row("SELECT COALESCE(MAX(id), 0) FROM billable_entries", NO_PARAMS, |row| row.get(0))
+            .context(Sqlite)?;
+
+        return Ok((id as u64).max(self.get_last_entry_id()?) + 1);
+    }
+
+    fn remove_entry(&self, entry: &BillableEntry) -> Result<bool, ProjectDataError> {
+        let removed = self.connection.execute(
+            "DELETE FROM billable_entries WHERE id = (
+                SELECT MAX(id) FROM billable_entries WHERE project_id = ?1 AND task = ?2 AND quantity = ?3 AND date = ?4
+                AND (id = ?5 OR ?5 = 0)
+            )",
+            params![entry.project_id, entry.task, entry.quantity as f64, entry.date, entry.id as i64]
+        ).context(Sqlite)?;
+
+        return Ok(removed > 0);
+    }
+
+    fn get_selected_project(&self) -> Result<Option<String>, ProjectDataError> {
+        return self.connection
+            .query_row("SELECT value FROM settings WHERE key = ?1", params![SELECTED_PROJECT_KEY], |row| row.get(0))
+            .optional()
+            .context(Sqlite);
+    }
+
+    fn set_selected_project(&self, project_id: &String) -> Result<(), ProjectDataError> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            params![SELECTED_PROJECT_KEY, project_id]
+        ).context(Sqlite)?;
+
+        return Ok(());
+    }
+
+    fn clear_selected_project(&self) -> Result<(), ProjectDataError> {
+        self.connection.execute("DELETE FROM settings WHERE key = ?1", params![SELECTED_PROJECT_KEY]).context(Sqlite)?;
+
+        return Ok(());
+    }
+
+    fn get_invoice_sequence(&self) -> Result<InvoiceSequence, ProjectDataError> {
+        let mut statement = self.connection
+            .prepare("SELECT number FROM invoice_numbers ORDER BY rowid")
+            .context(Sqlite)?;
+        let used = statement
+            .query_map(NO_PARAMS, |row| row.get(0))
+            .context(Sqlite)?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()
+            .context(Sqlite)?;
+
+        return Ok(InvoiceSequence {
+            last: self.get_setting(INVOICE_SEQUENCE_LAST_KEY)?.and_then(|last| last.parse().ok()).unwrap_or(0),
+            year: self.get_setting(INVOICE_SEQUENCE_YEAR_KEY)?.and_then(|year| year.parse().ok()),
+            used
+        });
+    }
+
+    fn set_invoice_sequence(&self, sequence: InvoiceSequence) -> Result<(), ProjectDataError> {
+        self.set_setting(INVOICE_SEQUENCE_LAST_KEY, Some(sequence.last.to_string()))?;
+        self.set_setting(INVOICE_SEQUENCE_YEAR_KEY, sequence.year.map(|year| year.to_string()))?;
+
+        self.connection.execute("DELETE FROM invoice_numbers", NO_PARAMS).context(Sqlite)?;
+        for number in sequence.used.iter() {
+            self.connection.execute("INSERT INTO invoice_numbers (number) VALUES (?1)", params![number]).context(Sqlite)?;
+        }
+
+        return Ok(());
+    }
+
+    fn get_invoices(&self) -> Result<Vec<Invoice>, ProjectDataError> {
+        let mut statement = self.connection
+            .prepare("SELECT number, project_id, customer, date, service_start, service_end, net, tax_rate, tax, gross, status, lex_office_id,
+                cancels, submission_queued, submission_attempts, submission_error, submission_rejected, lex_office_number FROM invoices ORDER BY rowid")
+            .context(Sqlite)?;
+        let invoices = statement
+            .query_map(NO_PARAMS, |row| Ok(Invoice {
+                number: row.get(0)?,
+                cancels: row.get(12)?,
+                project_id: row.get(1)?,
+                customer: row.get::<_, String>(2)?.lines().map(String::from).collect(),
+                date: row.get(3)?,
+                service_period: match (row.get::<_, Option<String>>(4)?, row.get::<_, Option<String>>(5)?) {
+                    (Some(start), Some(end)) => Some(ServicePeriod { start, end }),
+                    _ => None
+                },
+                line_items: vec![],
+                net: row.get(6)?,
+                tax_rate: row.get::<_, f64>(7)? as f32,
+                tax: row.get(8)?,
+                gross: row.get(9)?,
+                status: SqliteStore::status_from_str(&row.get::<_, String>(10)?, 10)?,
+                lex_office_id: row.get(11)?,
+                lex_office_number: row.get(17)?,
+                submission: match row.get::<_, Option<String>>(13)? {
+                    Some(queued) => Some(Submission { queued, attempts: row.get::<_, i64>(14)? as u32, last_error: row.get(15)?, rejected: row.get(16)? }),
+                    None => None
+                },
+                entries: vec![]
+            }))
+            .context(Sqlite)?
+            .collect::<Result<Vec<Invoice>, rusqlite::Error>>()
+            .context(Sqlite)?;
+
+        return invoices.into_iter()
+            .map(|invoice| Ok(Invoice {
+                line_items: self.get_line_items(&invoice.number)?,
+                entries: self.get_invoice_entries(&invoice.number)?,
+                ..invoice
+            }))
+            .collect();
+    }
+
+    fn insert_invoice(&self, invoice: Invoice) -> Result<(), ProjectDataError> {
+        let (service_start, service_end) = match &invoice.service_period {
+            Some(period) => (Some(&period.start), Some(&period.end)),
+            None => (None, None)
+        };
+        let (submission_queued, submission_attempts, submission_error, submission_rejected) = match &invoice.submission {
+            Some(submission) => (Some(&submission.queued), submission.attempts as i64, submission.last_error.as_ref(), submission.rejected),
+            None => (None, 0, None, false)
+        };
+        self.connection.execute(
+            "INSERT INTO invoices (number, project_id, customer, date, service_start, service_end, net, tax_rate, tax, gross, status, lex_office_id,
+             cancels, submission_queued, submission_attempts, submission_error, submission_rejected, lex_office_number)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
+             ON CONFLICT(number) DO UPDATE SET project_id = excluded.project_id, customer = excluded.customer, date = excluded.date,
+             service_start = excluded.service_start, service_end = excluded.service_end, net = excluded.net, tax_rate = excluded.tax_rate,
+             tax = excluded.tax, gross = excluded.gross, status = excluded.status, lex_office_id = excluded.lex_office_id, cancels = excluded.cancels,
+             submission_queued = excluded.submission_queued, submission_attempts = excluded.submission_attempts,
+             submission_error = excluded.submission_error, submission_rejected = excluded.submission_rejected,
+             lex_office_number = excluded.lex_office_number",
+            params![
+                invoice.number, invoice.project_id, invoice.customer.join("\n"), invoice.date, service_start, service_end,
+                invoice.net, invoice.tax_rate as f64, invoice.tax, invoice.gross, SqliteStore::status_to_str(&invoice.status), invoice.lex_office_id,
+                invoice.cancels, submission_queued, submission_attempts, submission_error, submission_rejected, invoice.lex_office_number
+            ]
+        ).context(Sqlite)?;
+
+        self.connection.execute("DELETE FROM invoice_line_items WHERE invoice_number = ?1", params![invoice.number]).context(Sqlite)?;
+        for (position, item) in invoice.line_items.iter().enumerate() {
+            self.connection.execute(
+                "INSERT INTO invoice_line_items (invoice_number, position, description, quantity, unit, unit_price) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![invoice.number, position as i64, item.description, item.quantity as f64, SqliteStore::unit_to_str(&item.unit), item.unit_price as i64]
+            ).context(Sqlite)?;
+        }
+        self.connection.execute("DELETE FROM invoice_entries WHERE invoice_number = ?1", params![invoice.number]).context(Sqlite)?;
+        for entry_id in invoice.entries.iter() {
+            self.connection.execute(
+                "INSERT INTO invoice_entries (invoice_number, entry_id) VALUES (?1, ?2)",
+                params![invoice.number, *entry_id as i64]
+            ).context(Sqlite)?;
+        }
+
+        return Ok(());
+    }
+
+    fn snapshot(&self) -> Result<ProjectData, ProjectDataError> {
+        return Ok(ProjectData {
+            billable: self.get_all_entries()?,
+            projects: self.get_projects()?,
+            selected_project: self.get_selected_project()?,
+            invoice_numbers: self.get_invoice_sequence()?,
+            last_entry_id: self.get_last_entry_id()?,
+            invoices: self.get_invoices()?
+        });
+    }
+
+    fn replace(&self, data: ProjectData) -> Result<(), ProjectDataError> {
+        self.connection.execute_batch("DELETE FROM invoice_entries;
+            DELETE FROM invoice_line_items;
+            DELETE FROM invoices;
+            DELETE FROM billable_entries;
+            DELETE FROM project_aliases;
+            DELETE FROM tasks;
+            DELETE FROM projects;
+            DELETE FROM settings;").context(Sqlite)?;
+
+        for (project_id, project) in data.projects.into_iter() {
+            self.insert_project(&project_id, project)?;
+        }
+        for entry in data.billable.into_iter() {
+            self.insert_entry(entry)?;
+        }
+        if data.last_entry_id > self.get_last_entry_id()? {
+            self.set_setting(LAST_ENTRY_ID_KEY, Some(data.last_entry_id.to_string()))?;
+        }
+        if let Some(project_id) = data.selected_project {
+            self.set_selected_project(&project_id)?;
+        }
+        self.set_invoice_sequence(data.invoice_numbers)?;
+        for invoice in data.invoices.into_iter() {
+            self.insert_invoice(invoice)?;
+        }
+
+        return Ok(());
+    }
+
+    fn backup(&self, target: &String) -> Result<(), ProjectDataError> {
+        // A separate connection only sees what has been committed
+        let connection = Connection::open(&self.path).context(Sqlite)?;
+        connection.execute("VACUUM INTO ?1", params![target]).context(Sqlite)?;
+
+        return Ok(());
+    }
+
+    fn save(&self) -> Result<(), ProjectDataError> {
+        return self.connection.execute_batch("COMMIT; BEGIN IMMEDIATE").context(Sqlite);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+    use crate::domain::objects::project_data::BillableUnit::Day;
+
+    fn temp_db_path(name: &str) -> String {
+        let path = env::temp_dir().join(format!("hours-sqlite-{}-{}.sqlite", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+
+        return path.to_string_lossy().to_string();
+    }
+
+    fn mock_data() -> ProjectData {
+        let mut tasks = HashSet::new();
+        tasks.insert("development".to_string());
+
+        let mut data = ProjectData::default();
+        data.projects.insert("foo".to_string(), Project { name: "Foo".to_string(), unit_price: 80, unit: Day, tasks, aliases: vec!["bar".to_string()], address: vec!["Foo GmbH".to_string(), "Hauptstraße 1".to_string()], grouping: LineGrouping::Week });
+        data.invoice_numbers = InvoiceSequence { last: 2, year: Some(2020), used: vec!["RE-2020-0001".to_string(), "RE-2020-0002".to_string()] };
+        data.billable.push(BillableEntry {
+            id: 1,
+            project_id: "foo".to_string(),
+            task: "development".to_string(),
+            quantity: 7.5,
+            date: "2020-10-11 22:09:24.269707 +02:00".to_string()
+        });
+        data.last_entry_id = 1;
+        data.selected_project = Some("foo".to_string());
+        data.invoices.push(Invoice {
+            number: "RE-2020-0002".to_string(),
+            cancels: None,
+            project_id: "foo".to_string(),
+            customer: vec!["Foo GmbH".to_string(), "Hauptstraße 1".to_string()],
+            date: "2020-10-31".to_string(),
+            service_period: Some(ServicePeriod { start: "2020-10-11".to_string(), end: "2020-10-11".to_string() }),
+            line_items: vec![LineItem { description: "development".to_string(), quantity: 7.5, unit: Day, unit_price: 80 }],
+            net: 60000,
+            tax_rate: 19.0,
+            tax: 11400,
+            gross: 71400,
+            status: InvoiceStatus::Draft,
+            lex_office_id: Some("voucher-id".to_string()),
+            lex_office_number: Some("RE0001".to_string()),
+            submission: Some(Submission { queued: "2020-10-31 10:00:00 +01:00".to_string(), attempts: 1, last_error: Some("offline".to_string()), rejected: false }),
+            entries: vec![1]
+        });
+
+        return data;
+    }
+
+    #[test]
+    fn test_migrations_set_user_version() {
+        let path = temp_db_path("migrations");
+        let store = SqliteStore::new(path.clone()).unwrap();
+
+        let version: i64 = store.connection.query_row("PRAGMA user_version", NO_PARAMS, |row| row.get(0)).unwrap();
+
+        assert_eq!(version, MIGRATIONS.len() as i64);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_migrate_legacy_project_ids() {
+        let path = temp_db_path("legacy_ids");
+        let mut connection = Connection::open(&path).unwrap();
+        let slug_migration = MIGRATIONS.iter().position(|m| matches!(m, Migration::Data(_))).unwrap();
+        SqliteStore::migrate_to(&mut connection, slug_migration).unwrap();
+        connection.execute_batch("INSERT INTO projects (id, name, unit_price, unit) VALUES ('foo bar', 'Foo Bar', 80, 'day');
+            INSERT INTO tasks (project_id, name) VALUES ('foo bar', 'development');
+            INSERT INTO project_aliases (alias, project_id) VALUES ('bar', 'foo bar');
+            INSERT INTO billable_entries (project_id, task, quantity, date) VALUES ('foo bar', 'development', 7.5, '2020-10-11 22:09:24.269707 +02:00');
+            INSERT INTO settings (key, value) VALUES ('selected_project', 'foo bar');").unwrap();
+        drop(connection);
+
+        let migrated = SqliteStore::new(path.clone()).unwrap().snapshot().unwrap();
+
+        assert_eq!(migrated.projects.keys().collect::<Vec<&String>>(), vec!["foo-bar"]);
+        assert_eq!(migrated.projects["foo-bar"].tasks.len(), 1);
+        assert_eq!(migrated.projects["foo-bar"].aliases, vec!["bar".to_string()]);
+        assert_eq!(migrated.billable[0].project_id, "foo-bar");
+        assert_eq!(migrated.selected_project, Some("foo-bar".to_string()));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_replace_and_snapshot() {
+        let path = temp_db_path("replace");
+        let store = SqliteStore::new(path.clone()).unwrap();
+
+        store.replace(mock_data()).unwrap();
+
+        assert_eq!(store.snapshot().unwrap(), mock_data());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_data_survives_reopen() {
+        let path = temp_db_path("reopen");
+        let store = SqliteStore::new(path.clone()).unwrap();
+        store.replace(mock_data()).unwrap();
+        store.save().unwrap();
+        drop(store);
+
+        let reopened = SqliteStore::new(path.clone()).unwrap();
+
+        assert_eq!(reopened.get_entries(&"foo".to_string()).unwrap(), mock_data().billable);
+        assert_eq!(reopened.get_selected_project().unwrap(), Some("foo".to_string()));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_insert_task_unknown_project() {
+        let path = temp_db_path("unknown_project");
+        let store = SqliteStore::new(path.clone()).unwrap();
+
+        assert_eq!(store.insert_task(&"bar".to_string(), &"development".to_string()).unwrap(), false);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_remove_entry() {
+        let path = temp_db_path("remove_entry");
+        let store = SqliteStore::new(path.clone()).unwrap();
+        let entry = BillableEntry { id: 2, ..mock_data().billable[0].clone() };
+        store.replace(mock_data()).unwrap();
+        store.insert_entry(entry.clone()).unwrap();
+
+        assert_eq!(store.remove_entry(&entry).unwrap(), true);
+        assert_eq!(store.snapshot().unwrap(), ProjectData { last_entry_id: 2, ..mock_data() });
+        assert_eq!(store.next_entry_id().unwrap(), 3);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_unknown_values_are_decode_errors() {
+        let path = temp_db_path("decode");
+        let store = SqliteStore::new(path.clone()).unwrap();
+        store.replace(mock_data()).unwrap();
+
+        store.connection.execute("UPDATE projects SET unit = 'week'", NO_PARAMS).unwrap();
+        assert_eq!(store.get_project(&"foo".to_string()).is_err(), true);
+        store.connection.execute("UPDATE projects SET unit = 'day', unit_price = 70000", NO_PARAMS).unwrap();
+        assert_eq!(store.get_project(&"foo".to_string()).is_err(), true);
+        store.connection.execute("UPDATE projects SET unit_price = 80, grouping = 'month'", NO_PARAMS).unwrap();
+        assert_eq!(store.get_project(&"foo".to_string()).is_err(), true);
+        store.connection.execute("UPDATE invoice_line_items SET unit = 'week'", NO_PARAMS).unwrap();
+        assert_eq!(store.get_invoices().is_err(), true);
+        store.connection.execute("UPDATE invoice_line_items SET unit = 'day'", NO_PARAMS).unwrap();
+        store.connection.execute("UPDATE invoices SET status = 'overdue'", NO_PARAMS).unwrap();
+        assert_eq!(store.get_invoices().is_err(), true);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_read_snapshot_leaves_file_untouched() {
+        let path = temp_db_path("read_snapshot");
+        let other_path = temp_db_path("read_snapshot_other");
+        let store = SqliteStore::new(path.clone()).unwrap();
+        store.replace(mock_data()).unwrap();
+        store.save().unwrap();
+        drop(store);
+        let content = fs::read(&path).unwrap();
+        fs::write(&other_path, "projects: {}").unwrap();
+
+        assert_eq!(SqliteStore::read_snapshot(&path).unwrap(), mock_data());
+        assert_eq!(fs::read(&path).unwrap(), content);
+        assert_eq!(SqliteStore::read_snapshot(&other_path).is_err(), true);
+        fs::write(&other_path, "").unwrap();
+        assert_eq!(SqliteStore::read_snapshot(&other_path).is_err(), true);
+        assert_eq!(fs::read(&other_path).unwrap().len(), 0);
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&other_path);
+    }
+
+    #[test]
+    fn test_unsaved_changes_are_discarded() {
+        let path = temp_db_path("unsaved");
+        let store = SqliteStore::new(path.clone()).unwrap();
+        store.replace(mock_data()).unwrap();
+        drop(store);
+
+        let reopened = SqliteStore::new(path.clone()).unwrap();
+
+        assert_eq!(reopened.snapshot().unwrap(), ProjectData::default());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_backup_contains_saved_state() {
+        let path = temp_db_path("backup");
+        let backup_path = temp_db_path("backup_target");
+        let store = SqliteStore::new(path.clone()).unwrap();
+        store.replace(mock_data()).unwrap();
+        store.save().unwrap();
+        store.replace(ProjectData::default()).unwrap();
+
+        store.backup(&backup_path).unwrap();
+        drop(store);
+
+        assert_eq!(SqliteStore::new(backup_path.clone()).unwrap().snapshot().unwrap(), mock_data());
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&backup_path);
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use handlebars::Handlebars;
+use snafu::ResultExt;
+
+use crate::constants::{CURRENCY, TEMPLATE_DIRECTORY};
+use crate::domain::errors::template::{TemplateError, ParseTemplate, ReadTemplate, RenderTemplate, ResolveTemplateDirectory, UnknownTemplate};
+use crate::domain::objects::Config;
+use crate::domain::objects::document::{EntryDocument, InvoiceDocument, LineItemDocument, ProjectDocument};
+use crate::domain::objects::invoice::{format_amount, Invoice};
+use crate::domain::objects::project_data::{Billable, Project};
+use crate::services::file_path_service::FilePathService;
+
+/// Templates shipped with hours, templates with the same name in the template directory replace them.
+const BUNDLED: &[(&str, &str)] = &[
+    ("invoice.html", include_str!("../../templates/invoice.html")),
+    ("invoice.md", include_str!("../../templates/invoice.md")),
+    ("invoice.typ", include_str!("../../templates/invoice.typ")),
+    ("timesheet.html", include_str!("../../templates/timesheet.html")),
+    ("timesheet.md", include_str!("../../templates/timesheet.md")),
+    ("timesheet.typ", include_str!("../../templates/timesheet.typ"))
+];
+
+#[derive(Debug, PartialEq)]
+pub struct Template {
+    /// File name, its extension decides how values are escaped
+    pub name: String,
+    pub content: String
+}
+
+
+pub struct TemplateService;
+
+impl TemplateService {
+    /// Data the templates are rendered with, see `InvoiceDocument`.
+    /// Line items and amounts are the ones of `invoice`, `billing` are the entries it covers.
+    pub fn document(invoice: &Invoice, config: &Config, project: &Project, billing: &[Billable]) -> InvoiceDocument {
+        let mut entries: Vec<&Billable> = billing.iter().collect();
+        entries.sort_by_key(|b| b.date);
+
+        return InvoiceDocument {
+            number: invoice.number.to_string(),
+            sender: config.sender.clone(),
+            bank: config.bank.clone(),
+            project: ProjectDocument {
+                id: invoice.project_id.to_string(),
+                name: project.name.to_string(),
+                unit: project.unit.clone(),
+                unit_price: format_amount(project.unit_price as i64 * 100),
+                address: project.address.clone()
+            },
+            date: invoice.date.to_string(),
+            service_period: invoice.service_period.clone(),
+            currency: CURRENCY,
+            line_items: invoice.line_items.iter()
+                .map(|item| LineItemDocument {
+                    description: item.description.to_string(),
+                    quantity: item.quantity,
+                    unit: item.unit.clone(),
+                    unit_price: format_amount(item.unit_price as i64 * 100),
+                    net: format_amount(item.net()),
+                    net_cents: item.net()
+                })
+                .collect(),
+            entries: entries.into_iter()
+                .map(|b| EntryDocument { date: b.date.format("%Y-%m-%d").to_string(), task: b.task.to_string(), quantity: b.quantity })
+                .collect(),
+            total_quantity: billing.iter().map(|b| b.quantity).sum(),
+            tax_rate: invoice.tax_rate.to_string(),
+            net: format_amount(invoice.net),
+            net_cents: invoice.net,
+            tax: format_amount(invoice.tax),
+            tax_cents: invoice.tax,
+            gross: format_amount(invoice.gross),
+            gross_cents: invoice.gross
+        }
+    }
+
+    /// Finds the template `name` in `~/.hou-rs/templates`, then among the bundled ones.
+    /// A name without extension gets the extension of the `out` file, so `invoice` and `out.md` pick `invoice.md`.
+    pub fn find(name: &str, out: &str) -> Result<Template, TemplateError> {
+        let directory = FilePathService::absolute_path(&TEMPLATE_DIRECTORY.to_string()).context(ResolveTemplateDirectory)?;
+
+        return TemplateService::find_in(Path::new(&directory), name, out);
+    }
+
+    fn find_in(directory: &Path, name: &str, out: &str) -> Result<Template, TemplateError> {
+        let mut candidates = vec![name.to_string()];
+        if let (None, Some(extension)) = (Path::new(name).extension(), Path::new(out).extension()) {
+            candidates.insert(0, format!("{}.{}", name, extension.to_string_lossy()));
+        }
+
+        for candidate in candidates.iter() {
+            let path = directory.join(candidate);
+            if path.is_file() {
+                let content = fs::read_to_string(&path).context(ReadTemplate { path: path.to_string_lossy() })?;
+                return Ok(Template { name: candidate.to_string(), content })
+            }
+            if let Some((bundled, content)) = BUNDLED.iter().find(|(bundled, _)| bundled == candidate) {
+                return Ok(Template { name: bundled.to_string(), content: content.to_string() })
+            }
+        }
+
+        return UnknownTemplate { name, available: TemplateService::available(directory) }.fail();
+    }
+
+    fn available(directory: &Path) -> Vec<String> {
+        let mut names: BTreeSet<String> = BUNDLED.iter().map(|(name, _)| name.to_string()).collect();
+        if let Ok(files) = fs::read_dir(directory) {
+            names.extend(files.filter_map(Result::ok).map(|file| file.file_name().to_string_lossy().to_string()));
+        }
+
+        return names.into_iter().collect();
+    }
+
+    /// Renders a handlebars template. Values are escaped for HTML, Markdown or Typst by the template's extension,
+    /// and fields that do not exist are an error so that typos do not go unnoticed.
+    pub fn render(template: &Template, document: &InvoiceDocument) -> Result<String, TemplateError> {
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(true);
+        match Path::new(&template.name).extension().and_then(|e| e.to_str()) {
+            Some("html") | Some("htm") => handlebars.register_escape_fn(handlebars::html_escape),
+            Some("md") => handlebars.register_escape_fn(escape_markdown),
+            Some("typ") => handlebars.register_escape_fn(escape_typst),
+            _ => handlebars.register_escape_fn(handlebars::no_escape)
+        }
+
+        handlebars.register_template_string(&template.name, &template.content)
+            .map_err(Box::new)
+            .context(ParseTemplate { name: &template.name })?;
+
+        return handlebars.render(&template.name, document)
+            .map_err(Box::new)
+            .context(RenderTemplate { name: &template.name });
+    }
+}
+
+fn escape_with(text: &str, special: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    return escaped;
+}
+
+fn escape_markdown(text: &str) -> String {
+    return escape_with(text, "\\`*_[]<>#|~");
+}
+
+fn escape_typst(text: &str) -> String {
+    return escape_with(text, "\\#*_$@<>[]`~/");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+    use std::env;
+    use chrono::{Local, NaiveDate, TimeZone};
+    use crate::domain::objects::{BankDetails, Sender};
+    use crate::domain::objects::invoice::ServicePeriod;
+    use crate::services::invoice_service::InvoiceService;
+    use crate::domain::objects::project_data::{BillableUnit, LineGrouping};
+
+    fn document() -> InvoiceDocument {
+        let config = Config {
+            sender: Some(Sender { name: "Me".to_string(), address: vec!["Hauptstraße 1".to_string()], tax_id: None }),
+            bank: Some(BankDetails { account_holder: "Me".to_string(), iban: "DE00".to_string(), bic: None, bank_name: None }),
+            ..Config::default()
+        };
+        let project = Project {
+            name: "Foo & Bar".to_string(),
+            unit_price: 80,
+            unit: BillableUnit::Hour,
+            tasks: HashSet::new(),
+            aliases: vec![],
+            address: vec!["Foo_Bar GmbH".to_string()],
+            grouping: LineGrouping::Task
+        };
+        let billing = vec![
+            Billable { id: 1, project_id: "foo".to_string(), task: "review".to_string(), quantity: 1.0, date: Local.ymd(2020, 10, 12).and_hms(9, 0, 0) },
+            Billable { id: 2, project_id: "foo".to_string(), task: "dev".to_string(), quantity: 2.5, date: Local.ymd(2020, 10, 11).and_hms(9, 0, 0) }
+        ];
+
+        let invoice = InvoiceService::invoice("RE-2020-0001", 19.0, &"foo".to_string(), &project, &billing, NaiveDate::from_ymd(2020, 10, 31), None);
+
+        return TemplateService::document(&invoice, &config, &project, &billing);
+    }
+
+    #[test]
+    fn test_document() {
+        let document = document();
+
+        assert_eq!(document.entries.iter().map(|e| e.date.as_str()).collect::<Vec<&str>>(), vec!["2020-10-11", "2020-10-12"]);
+        assert_eq!(document.line_items.len(), 2);
+        assert_eq!(document.total_quantity, 3.5);
+        assert_eq!(document.gross, "333.20 EUR".to_string());
+        assert_eq!(document.service_period, Some(ServicePeriod { start: "2020-10-11".to_string(), end: "2020-10-12".to_string() }));
+    }
+
+    #[test]
+    fn test_render_bundled_templates() {
+        let document = document();
+        let unconfigured = InvoiceDocument { sender: None, bank: None, service_period: None, ..self::document() };
+
+        for (name, content) in BUNDLED.iter() {
+            TemplateService::render(&Template { name: name.to_string(), content: content.to_string() }, &unconfigured).unwrap();
+            let rendered = TemplateService::render(&Template { name: name.to_string(), content: content.to_string() }, &document).unwrap();
+
+            let expected = if name.starts_with("timesheet") { "2020-10-11" } else { "333.20 EUR" };
+            assert_eq!(rendered.contains("2020-10-31"), true, "{}", name);
+            assert_eq!(rendered.contains(expected), true, "{}", name);
+            assert_eq!(rendered.contains("RE-2020-0001"), true, "{}", name);
+            assert_eq!(rendered.contains("2020-10-12"), true, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_render_escapes_by_extension() {
+        let render = |name: &str| TemplateService::render(
+            &Template { name: name.to_string(), content: "{{project.name}} {{project.address.[0]}}".to_string() },
+            &document()
+        ).unwrap();
+
+        assert_eq!(render("a.html"), "Foo &amp; Bar Foo_Bar GmbH");
+        assert_eq!(render("a.md"), "Foo & Bar Foo\\_Bar GmbH");
+        assert_eq!(render("a.txt"), "Foo & Bar Foo_Bar GmbH");
+    }
+
+    #[test]
+    fn test_render_unknown_field_fails() {
+        let template = Template { name: "a.md".to_string(), content: "{{project.nmae}}".to_string() };
+
+        assert_eq!(TemplateService::render(&template, &document()).is_err(), true);
+    }
+
+    #[test]
+    fn test_find_prefers_own_templates() {
+        let directory = env::temp_dir().join("hours_test_templates");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("invoice.md"), "mine").unwrap();
+
+        assert_eq!(TemplateService::find_in(&directory, "invoice", "out.md").unwrap().content, "mine");
+        assert_eq!(TemplateService::find_in(&directory, "invoice", "out.html").unwrap().name, "invoice.html");
+        assert_eq!(TemplateService::find_in(&directory, "timesheet.typ", "out.pdf").unwrap().name, "timesheet.typ");
+        assert_eq!(TemplateService::find_in(&directory, "missing", "out.md").is_err(), true);
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
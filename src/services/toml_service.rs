@@ -9,6 +9,7 @@ use crate::domain::errors::toml_file::{FileError, SerializeToml, DeserializeToml
 pub trait TomlFileServiceTrait {
     fn new(file_service: FileService) -> Self;
     fn read_from_file<T>(&self, file: &String) -> Result<T, FileError> where T: DeserializeOwned;
+    #[allow(dead_code)]
     fn save_to_file<T>(&self, config: T, file_name: &String) -> Result<(), FileError> where T: Serialize;
 }
 
@@ -26,7 +27,15 @@ impl TomlFileServiceTrait for TomlFileService {
     fn read_from_file<T>(&self, file_name: &String) -> Result<T, FileError> where T: DeserializeOwned {
         let content = self.file_service.read_file_to_string(file_name)?;
 
-        return toml::from_str(&content).map_err(|e| DeserializeToml { path: file_name }.into_error(e));
+        return toml::from_str(&content).map_err(|e| {
+            let position = e.line_col().map(|(line, column)| (line + 1, column + 1));
+
+            DeserializeToml {
+                path: file_name,
+                line: position.map(|(line, _)| line),
+                column: position.map(|(_, column)| column)
+            }.into_error(e)
+        });
     }
 
     fn save_to_file<T>(&self, config: T, file_name: &String) -> Result<(), FileError> where T: Serialize {
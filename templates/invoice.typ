@@ -0,0 +1,52 @@
+#set page(paper: "a4")
+#set text(size: 10pt)
+
+{{#if sender}}{{#with sender}}
+#align(right)[
+  *{{name}}* \
+  {{#each address}}
+  {{this}} \
+  {{/each}}
+  {{#if tax_id}}
+  {{tax_id}}
+  {{/if}}
+]
+{{/with}}{{/if}}
+
+#v(2em)
+{{#each project.address}}
+{{this}} \
+{{/each}}
+
+#v(2em)
+= Invoice {{number}}
+
+Date: {{date}}{{#if service_period}}{{#with service_period}} \
+Service period: {{start}} to {{end}}{{/with}}{{/if}}
+
+#table(
+  columns: (1fr, auto, auto, auto),
+  align: (left, right, right, right),
+  stroke: none,
+  [*Description*], [*Quantity*], [*Unit price*], [*Amount*],
+  table.hline(),
+{{#each line_items}}
+  [{{description}}], [{{quantity}} {{unit}}], [{{unit_price}}], [{{net}}],
+{{/each}}
+  table.hline(),
+  [], [], [Net], [{{net}}],
+  [], [], [VAT {{tax_rate}}%], [{{tax}}],
+  [], [], [*Total*], [*{{gross}}*],
+)
+{{#if bank}}{{#with bank}}
+
+Please transfer the total to: \
+{{account_holder}} \
+IBAN: {{iban}} \
+{{#if bic}}
+BIC: {{bic}} \
+{{/if}}
+{{#if bank_name}}
+{{bank_name}}
+{{/if}}
+{{/with}}{{/if}}
@@ -0,0 +1,19 @@
+#set page(paper: "a4")
+#set text(size: 10pt)
+
+= Timesheet {{project.name}}
+
+{{#if sender}}{{#with sender}}{{name}}, {{/with}}{{/if}}{{date}}, invoice {{number}}{{#if service_period}}{{#with service_period}}, {{start}} to {{end}}{{/with}}{{/if}}
+
+#table(
+  columns: (auto, 1fr, auto),
+  align: (left, left, right),
+  stroke: none,
+  [*Date*], [*Task*], [*Quantity ({{project.unit}})*],
+  table.hline(),
+{{#each entries}}
+  [{{date}}], [{{task}}], [{{quantity}}],
+{{/each}}
+  table.hline(),
+  [*Total*], [], [*{{total_quantity}}*],
+)